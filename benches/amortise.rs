@@ -0,0 +1,86 @@
+use chrono::NaiveDate;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use loan_amortisation_rust::amortise::{InterestMethod, LoanSpec};
+use rust_decimal_macros::dec;
+
+fn spec(interest_method: InterestMethod, num_payments: u32) -> LoanSpec {
+    LoanSpec::builder(
+        dec!(15000),
+        dec!(0.079),
+        num_payments,
+        NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+    )
+    .interest_method(interest_method)
+    .build()
+}
+
+/// Calculated payments: 30/360 takes the closed-form path, the actual-day methods iterate.
+fn bench_amortise(c: &mut Criterion) {
+    let mut group = c.benchmark_group("amortise");
+    for num_payments in [36, 120, 360] {
+        for interest_method in [
+            InterestMethod::Convention30_360,
+            InterestMethod::Actual365,
+            InterestMethod::ActualActualIsda,
+        ] {
+            let spec = spec(interest_method, num_payments);
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", interest_method), num_payments),
+                &spec,
+                |b, spec| b.iter(|| black_box(spec).amortise().unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+/// A single schedule pass with the payment given, the floor for any calculated payment.
+fn bench_fixed_payment(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fixed_payment");
+    for num_payments in [36, 120, 360] {
+        let spec = LoanSpec {
+            fixed_payment: Some(dec!(500)),
+            ..spec(InterestMethod::Convention30_360, num_payments)
+        };
+        group.bench_with_input(
+            BenchmarkId::from_parameter(num_payments),
+            &spec,
+            |b, spec| b.iter(|| black_box(spec).amortise().unwrap()),
+        );
+    }
+    group.finish();
+}
+
+/// Batch pricing: many quotes at different rates and terms.
+fn bench_quote_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quote_batch");
+    for interest_method in [InterestMethod::Convention30_360, InterestMethod::Actual365] {
+        let specs: Vec<LoanSpec> = (0..100)
+            .map(|i| LoanSpec {
+                annual_rate: dec!(0.049) + dec!(0.001) * rust_decimal::Decimal::from(i),
+                ..spec(interest_method, 24 + (i % 4) * 12)
+            })
+            .collect();
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{:?}", interest_method)),
+            &specs,
+            |b, specs| {
+                b.iter(|| {
+                    for spec in specs {
+                        black_box(spec.amortise().unwrap());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_amortise,
+    bench_fixed_payment,
+    bench_quote_batch
+);
+criterion_main!(benches);
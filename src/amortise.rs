@@ -1,23 +1,67 @@
+mod calendar;
+mod day_count;
+mod deal;
+mod error;
+mod floating;
+mod frequency;
 mod interest;
+mod observer;
+mod profile;
+mod rate;
+mod rfr;
 mod schedule;
 mod secant;
+mod solve;
+mod solver;
+mod spec;
+mod style;
 mod utils;
 
 use chrono::NaiveDate;
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
 
+pub use calendar::BusinessDayConvention;
+pub use calendar::Calendar;
+pub use calendar::HolidayCalendar;
+pub use calendar::HolidayList;
+pub use day_count::AccrualPeriod;
+pub use day_count::CustomDayCounter;
+pub use day_count::DayCounter;
+pub use deal::MotorFinanceDeal;
+pub use error::AmortisationError;
+pub use floating::FloatingRate;
+pub use floating::RateIndex;
+pub use frequency::DateGenerator;
+pub use frequency::PaymentFrequency;
+use interest::get_daily_interest_rate;
 pub use interest::InterestMethod;
 pub use interest::InterestType;
+use observer::NoopObserver;
+pub use observer::SolverIteration;
+pub use observer::SolverObserver;
+pub use profile::PaymentProfile;
+pub use rate::RateChange;
+pub use rate::RateChangePayment;
+pub use rfr::CompoundedRfr;
 use schedule::build_schedule;
+pub use schedule::Meta;
 pub use schedule::Payment;
 pub use schedule::Schedule;
-use secant::secant_method;
+use solver::find_root;
+pub use solver::SolverConfig;
+pub use solver::SolverReport;
+pub use spec::LoanBuilder;
+pub use spec::LoanSpec;
+pub use style::AmortisationStyle;
 use utils::round_decimal;
 
-const PERIODS_PER_YEAR: u32 = 12;
-const ESTIMATE_WINDOW: f32 = 2.5;
+const ESTIMATE_WINDOW: Decimal = Decimal::from_parts(25, 0, 0, false, 1);
+/// Payments are rounded to the penny, so estimates within half a penny are the same payment.
+const PAYMENT_X_TOLERANCE: Decimal = Decimal::from_parts(5, 0, 0, false, 3);
 
+/// Positional wrapper around [`LoanSpec::amortise`], kept for existing callers.
+#[allow(clippy::too_many_arguments)]
 pub fn amortise(
     principal: Decimal,
     annual_rate: Decimal,
@@ -30,96 +74,264 @@ pub fn amortise(
     fixed_payment: Option<Decimal>,
     balloon_payment: Option<Decimal>,
     option_fee: Option<Decimal>,
-) -> Schedule {
-    let period_payment = if let Some(fixed_payment) = fixed_payment {
-        // Use the provided fixed payment amount
-        fixed_payment
-    } else {
-        // Calculate payment using secant method as before
+) -> Result<Schedule, AmortisationError> {
+    LoanSpec {
+        fixed_payment,
+        balloon_payment,
+        option_fee,
+        ..LoanSpec::builder(
+            principal,
+            annual_rate,
+            num_payments,
+            disbursal_date,
+            first_payment_date,
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .interest_method(interest_method)
+        .interest_type(interest_type)
+        .build()
+    }
+    .amortise()
+}
+
+impl LoanSpec {
+    pub fn amortise(&self) -> Result<Schedule, AmortisationError> {
+        self.amortise_with_observer(&mut NoopObserver)
+    }
+
+    /// As [`LoanSpec::amortise`], reporting each payment solver iteration to `observer`.
+    pub fn amortise_with_observer(
+        &self,
+        observer: &mut dyn SolverObserver,
+    ) -> Result<Schedule, AmortisationError> {
+        self.validate()?;
+
+        // The contractual payment is set at the initial rate; rate changes then recast it or
+        // flex the term as the schedule is built
+        let initial_rate_spec = LoanSpec {
+            annual_rate: self.initial_rate(),
+            rate_changes: Vec::new(),
+            floating_rate: None,
+            ..self.clone()
+        };
+        let (period_payment, report) = if self.is_interest_only() {
+            // Every payment is interest only, so there's no payment to find
+            (Decimal::ZERO, None)
+        } else if self.amortisation_style == AmortisationStyle::EqualPrincipal {
+            // Each payment repays the same principal, with the period's interest on top
+            (self.principal_instalment(), None)
+        } else if let Some(fixed_payment) = self.fixed_payment {
+            // Use the provided fixed payment amount
+            (fixed_payment, None)
+        } else if let Some(period_payment) = initial_rate_spec.closed_form_payment()? {
+            (period_payment, None)
+        } else {
+            let (period_payment, report) = initial_rate_spec.solve_payment(observer)?;
+            (period_payment, Some(report))
+        };
+
+        if self.has_variable_rate() && self.rate_change_payment == RateChangePayment::FlexTerm {
+            let mut schedule = self.repay_with(period_payment)?;
+            schedule.meta.solver = report;
+            return Ok(schedule);
+        }
+
+        // Only settle balance if we calculated the payment AND no balloon payment. An
+        // interest-only loan always repays its principal with the last payment, and an equal
+        // principal loan repays its last instalment and any balloon payment together
+        let settle_balance = self.amortisation_style == AmortisationStyle::EqualPrincipal
+            || ((self.fixed_payment.is_none() || self.is_interest_only())
+                && self.balloon_payment.is_none());
+        let mut schedule = build_schedule(self, period_payment, settle_balance);
+        schedule.meta.solver = report;
+        Ok(schedule)
+    }
+
+    /// Finds the level payment by iterating over full schedules, for any interest method.
+    fn solve_payment(
+        &self,
+        observer: &mut dyn SolverObserver,
+    ) -> Result<(Decimal, SolverReport), AmortisationError> {
         // For balloon payment scenarios, reduce the principal by the balloon amount for calculation
-        let effective_principal = if let Some(balloon) = balloon_payment {
-            principal - balloon
+        let effective_principal = if let Some(balloon) = self.balloon_payment {
+            self.principal - balloon
         } else {
-            principal
+            self.principal
         };
-        
-        let mut period_payment = calculate_rough_period_payment(effective_principal, annual_rate, num_payments);
 
+        // The balance only falls once the interest-only periods are over, and a payment
+        // profile or advance payments spread the same repayments over base payments scaled by
+        // their weights
+        let weights =
+            self.amortising_weights().sum::<Decimal>() + Decimal::from(self.advance_payments);
+        let period_payment = calculate_rough_period_payment(
+            effective_principal,
+            self.annual_rate,
+            self.amortising_payments(),
+            self.frequency,
+        )? * Decimal::from(self.amortising_payments())
+            / weights;
+
+        let iteration_spec = self.iteration_spec();
+
+        let mut iteration = 0;
         let f = |period_payment| {
-            println!("Trying period payment: {}", period_payment);
-            let schedule = build_schedule(
-                principal,
-                disbursal_date,
-                first_capitalisation_date,
-                first_payment_date,
-                num_payments,
-                annual_rate,
-                period_payment,
-                interest_method,
-                interest_type,
-                false, // Don't settle balance in secant method iterations
-                None, // Don't apply balloon payment logic during secant iterations
-                None, // Don't apply option fee during secant iterations
-            );
-            let final_balance = schedule.payments.last().unwrap().balance;
-            // For balloon payment scenarios, we want the final balance to equal the balloon payment amount
-            if let Some(balloon) = balloon_payment {
-                final_balance - balloon // Target: balance should equal balloon payment
-            } else {
-                final_balance // Normal case: target is zero balance
-            }
+            let residual = residual(&iteration_spec, period_payment, self.balloon_payment)?;
+            iteration += 1;
+            observer.on_iteration(&SolverIteration {
+                iteration,
+                estimate: period_payment,
+                residual,
+            });
+            Ok(residual)
         };
 
-        let estimate_window = Decimal::from_f32(ESTIMATE_WINDOW).unwrap();
-        period_payment = match secant_method(
+        let root = find_root(
             f,
-            period_payment / estimate_window,
-            period_payment * estimate_window,
-            Decimal::new(1, 2),
-            4,
-        ) {
-            Some(root) => root,
-            None => {
-                println!("Failed to converge");
-                return Schedule::new();
-            }
+            period_payment / ESTIMATE_WINDOW,
+            period_payment * ESTIMATE_WINDOW,
+            &self.solver,
+            PAYMENT_X_TOLERANCE,
+        )?;
+
+        Ok((round_decimal(root.value, None, None, None), root.into()))
+    }
+
+    /// The level payment in closed form, for day counts where every period accrues at the
+    /// same rate.
+    ///
+    /// When each period is charged a fixed number of days whatever the dates, as under 30/360,
+    /// the annuity formula is exact apart from each period's interest being rounded to the
+    /// penny. Interest-only periods leave the balance unchanged, so the annuity runs over the
+    /// payments after them, and with a payment profile it's weighted payment by payment.
+    /// Advance payments are undiscounted. Date-based day counts return `None` and are solved
+    /// iteratively.
+    fn closed_form_payment(&self) -> Result<Option<Decimal>, AmortisationError> {
+        let day_counter = self.day_counter();
+        let Some(days) = day_counter.fixed_period_days(self.frequency) else {
+            return Ok(None);
         };
+        let period_rate =
+            get_daily_interest_rate(self.nominal_rate(), day_counter) * Decimal::from(days);
+        let balloon = self.balloon_payment.unwrap_or_default();
+        if self.payment_profile.is_some() || self.advance_payments > 0 {
+            return weighted_annuity_payment(
+                self.principal,
+                balloon,
+                period_rate,
+                Decimal::from(self.advance_payments),
+                self.amortising_weights(),
+            )
+            .map(Some);
+        }
+        annuity_payment(
+            self.principal,
+            balloon,
+            period_rate,
+            self.amortising_payments(),
+        )
+        .map(Some)
+    }
 
-        round_decimal(period_payment, None, None, None)
-    };
-
-    let schedule = build_schedule(
-        principal,
-        disbursal_date,
-        first_capitalisation_date,
-        first_payment_date,
-        num_payments,
-        annual_rate,
-        period_payment,
-        interest_method,
-        interest_type,
-        fixed_payment.is_none() && balloon_payment.is_none(), // Only settle balance if we calculated the payment AND no balloon payment
-        balloon_payment,
-        option_fee,
-    );
+    /// The payment profile's weights for the payments after the interest-only periods.
+    fn amortising_weights(&self) -> impl Iterator<Item = Decimal> + '_ {
+        (self.interest_only_periods + 1..=self.num_payments)
+            .map(|payment| self.payment_weight(payment))
+    }
+
+    /// Copy of the spec used while solving, without balloon payment or option fee logic.
+    fn iteration_spec(&self) -> LoanSpec {
+        LoanSpec {
+            balloon_payment: None,
+            option_fee: None,
+            ..self.clone()
+        }
+    }
+}
 
-    schedule
+/// How far `period_payment` misses the target final balance (zero, or the balloon payment).
+fn residual(
+    iteration_spec: &LoanSpec,
+    period_payment: Decimal,
+    balloon: Option<Decimal>,
+) -> Result<Decimal, AmortisationError> {
+    // Don't settle balance in solver iterations
+    let schedule = build_schedule(iteration_spec, period_payment, false);
+    let final_balance = schedule
+        .payments
+        .last()
+        .ok_or(AmortisationError::InvalidTerm(iteration_spec.num_payments))?
+        .balance;
+    // For balloon payment scenarios, we want the final balance to equal the balloon payment amount
+    if let Some(balloon) = balloon {
+        Ok(final_balance - balloon) // Target: balance should equal balloon payment
+    } else {
+        Ok(final_balance) // Normal case: target is zero balance
+    }
 }
 
 fn calculate_rough_period_payment(
     principal: Decimal,
     annual_rate: Decimal,
     num_payments: u32,
-) -> Decimal {
-    let one = Decimal::from(1);
-    let period_rate = annual_rate / Decimal::from(PERIODS_PER_YEAR);
-    let factor = (one + period_rate).powd(Decimal::from(num_payments));
-    round_decimal(
-        (principal * period_rate * factor) / (factor - one),
-        None,
-        None,
-        None,
-    )
+    frequency: PaymentFrequency,
+) -> Result<Decimal, AmortisationError> {
+    let period_rate = annual_rate / Decimal::from(frequency.periods_per_year());
+    annuity_payment(principal, Decimal::ZERO, period_rate, num_payments)
+}
+
+/// Level payment, rounded to the penny, that reduces `principal` to `balloon` over
+/// `num_payments` periods at `period_rate`.
+fn annuity_payment(
+    principal: Decimal,
+    balloon: Decimal,
+    period_rate: Decimal,
+    num_payments: u32,
+) -> Result<Decimal, AmortisationError> {
+    if period_rate.is_zero() {
+        return Ok(round_decimal(
+            (principal - balloon) / Decimal::from(num_payments),
+            None,
+            None,
+            None,
+        ));
+    }
+    let factor = (Decimal::ONE + period_rate)
+        .checked_powd(Decimal::from(num_payments))
+        .ok_or(AmortisationError::Overflow)?;
+    let payment = principal
+        .checked_mul(factor)
+        .map(|p| p - balloon)
+        .and_then(|p| p.checked_mul(period_rate))
+        .and_then(|p| p.checked_div(factor - Decimal::ONE))
+        .ok_or(AmortisationError::Overflow)?;
+    Ok(round_decimal(payment, None, None, None))
+}
+
+/// Base payment, rounded to the penny, that reduces `principal` to `balloon` at `period_rate`
+/// when `upfront` times the base is paid at the start and each period's payment is the base
+/// scaled by its weight in `weights`.
+fn weighted_annuity_payment(
+    principal: Decimal,
+    balloon: Decimal,
+    period_rate: Decimal,
+    upfront: Decimal,
+    weights: impl IntoIterator<Item = Decimal>,
+) -> Result<Decimal, AmortisationError> {
+    let discount = Decimal::ONE
+        .checked_div(Decimal::ONE + period_rate)
+        .ok_or(AmortisationError::Overflow)?;
+    // Present value of the weights, and the discount factor to the last payment
+    let mut factor = Decimal::ONE;
+    let mut weighted = upfront;
+    for weight in weights {
+        factor *= discount;
+        weighted += weight * factor;
+    }
+    let payment = (principal - balloon * factor)
+        .checked_div(weighted)
+        .ok_or(AmortisationError::Overflow)?;
+    Ok(round_decimal(payment, None, None, None))
 }
 
 #[cfg(test)]
@@ -134,11 +346,38 @@ mod tests {
         let annual_rate = Decimal::from_f64(8.9).unwrap() / Decimal::from(100);
         let num_payments = 36;
 
-        let period_payment = calculate_rough_period_payment(principal, annual_rate, num_payments);
+        let period_payment = calculate_rough_period_payment(
+            principal,
+            annual_rate,
+            num_payments,
+            PaymentFrequency::Monthly,
+        )
+        .unwrap();
 
         assert!(period_payment > Decimal::from(0));
     }
 
+    #[test]
+    fn test_calculate_rough_period_payment_zero_rate() {
+        let period_payment = calculate_rough_period_payment(
+            dec!(1200),
+            Decimal::ZERO,
+            12,
+            PaymentFrequency::Monthly,
+        )
+        .unwrap();
+
+        assert_eq!(period_payment, dec!(100));
+    }
+
+    #[test]
+    fn test_calculate_rough_period_payment_overflow() {
+        let result =
+            calculate_rough_period_payment(dec!(15000), dec!(10), 1200, PaymentFrequency::Monthly);
+
+        assert_eq!(result, Err(AmortisationError::Overflow));
+    }
+
     #[test]
     fn test_amortise_with_fixed_payment() {
         let principal = dec!(15000);
@@ -151,34 +390,31 @@ mod tests {
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
 
         // Test with fixed payment
-        let schedule_fixed = amortise(
+        let schedule_fixed = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            Some(fixed_payment),
-            None, // no balloon payment
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .fixed_payment(fixed_payment)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Test without fixed payment (calculated payment)
-        let schedule_calculated = amortise(
+        let schedule_calculated = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            None,
-            None, // no balloon payment
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Verify fixed payment schedule properties
         assert_eq!(schedule_fixed.payments.len(), num_payments as usize);
@@ -217,19 +453,18 @@ mod tests {
         let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
 
-        let schedule = amortise(
+        let schedule = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            Some(low_fixed_payment),
-            None, // no balloon payment
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .fixed_payment(low_fixed_payment)
+        .build()
+        .amortise()
+        .unwrap();
 
         // With low fixed payment, loan should not be fully paid off
         let final_balance = schedule.payments.last().unwrap().balance;
@@ -253,34 +488,31 @@ mod tests {
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
 
         // Test with balloon payment - should have lower monthly payments
-        let schedule_balloon = amortise(
+        let schedule_balloon = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            None, // calculated payment
-            Some(balloon_payment),
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .balloon_payment(balloon_payment)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Test without balloon payment for comparison
-        let schedule_normal = amortise(
+        let schedule_normal = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            None, // calculated payment
-            None, // no balloon payment
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Verify balloon payment schedule properties
         assert_eq!(schedule_balloon.payments.len(), num_payments as usize);
@@ -318,19 +550,19 @@ mod tests {
         let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
 
-        let schedule = amortise(
+        let schedule = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            Some(fixed_payment),
-            Some(balloon_payment),
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .fixed_payment(fixed_payment)
+        .balloon_payment(balloon_payment)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Verify schedule properties
         assert_eq!(schedule.payments.len(), num_payments as usize);
@@ -367,19 +599,18 @@ mod tests {
         let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
 
-        let schedule = amortise(
+        let schedule = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            None, // calculate optimal payment
-            Some(balloon_payment),
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .balloon_payment(balloon_payment)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Verify PCP schedule properties
         assert_eq!(schedule.payments.len(), num_payments as usize);
@@ -427,34 +658,31 @@ mod tests {
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
 
         // Test with option fee - should add fee to final payment
-        let schedule_with_fee = amortise(
+        let schedule_with_fee = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            None, // calculated payment
-            None, // no balloon payment
-            Some(option_fee),
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .option_fee(option_fee)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Test without option fee for comparison
-        let schedule_without_fee = amortise(
+        let schedule_without_fee = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            None, // calculated payment
-            None, // no balloon payment
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Verify option fee schedule properties
         assert_eq!(schedule_with_fee.payments.len(), num_payments as usize);
@@ -502,19 +730,19 @@ mod tests {
         let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
 
-        let schedule = amortise(
+        let schedule = LoanSpec::builder(
             principal,
             annual_rate,
             num_payments,
             disbursal_date,
             first_payment_date,
-            first_capitalisation_date,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            None, // calculate optimal payment
-            Some(balloon_payment),
-            Some(option_fee),
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .balloon_payment(balloon_payment)
+        .option_fee(option_fee)
+        .build()
+        .amortise()
+        .unwrap();
 
         // Verify HP schedule properties
         assert_eq!(schedule.payments.len(), num_payments as usize);
@@ -547,4 +775,1091 @@ mod tests {
         assert!(monthly_payment < dec!(350), 
                 "HP monthly payment should be reasonable for an £18k vehicle");
     }
+
+    #[test]
+    fn test_amortise_wrapper_matches_spec() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 1, 15).unwrap();
+
+        let from_wrapper = amortise(
+            dec!(20000),
+            dec!(0.06),
+            36,
+            disbursal_date,
+            first_payment_date,
+            first_capitalisation_date,
+            InterestMethod::Actual365,
+            InterestType::Compound,
+            None,
+            Some(dec!(5000)),
+            Some(dec!(10)),
+        )
+        .unwrap();
+
+        let from_spec = LoanSpec::builder(
+            dec!(20000),
+            dec!(0.06),
+            36,
+            disbursal_date,
+            first_payment_date,
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .interest_method(InterestMethod::Actual365)
+        .interest_type(InterestType::Compound)
+        .balloon_payment(dec!(5000))
+        .option_fee(dec!(10))
+        .build()
+        .amortise()
+        .unwrap();
+
+        assert_eq!(from_wrapper.payments.len(), from_spec.payments.len());
+        for (a, b) in from_wrapper.payments.iter().zip(from_spec.payments.iter()) {
+            assert_eq!(a.payment, b.payment);
+            assert_eq!(a.balance, b.balance);
+        }
+        assert_eq!(
+            from_wrapper.meta.total_payable,
+            from_spec.meta.total_payable
+        );
+    }
+
+    #[test]
+    fn test_amortise_rejects_invalid_spec() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+
+        let result = LoanSpec::builder(
+            dec!(10000),
+            dec!(0.05),
+            12,
+            disbursal_date,
+            first_payment_date,
+        )
+        .balloon_payment(dec!(12000))
+        .build()
+        .amortise();
+
+        assert!(matches!(
+            result,
+            Err(AmortisationError::BalloonExceedsPrincipal { .. })
+        ));
+    }
+
+    #[test]
+    fn test_amortise_single_payment() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        let spec = LoanSpec::builder(
+            dec!(1000),
+            dec!(0.05),
+            1,
+            disbursal_date,
+            first_payment_date,
+        )
+        .build();
+
+        // The only payment clears the balance, so the APR comes from £1,000 growing to
+        // £1,004.23 over 31 days: 1.00423^(365 / 31) - 1
+        let schedule = spec.amortise().unwrap();
+        assert_eq!(schedule.payments.len(), 1);
+        assert_eq!(schedule.payments[0].payment, dec!(1004.23));
+        assert_eq!(schedule.payments[0].balance, Decimal::ZERO);
+        assert_eq!(schedule.meta.calculated_apr, dec!(0.050956));
+
+        let no_payments = LoanSpec {
+            num_payments: 0,
+            ..spec.iteration_spec()
+        };
+        assert_eq!(
+            residual(&no_payments, dec!(1000), None),
+            Err(AmortisationError::InvalidTerm(0))
+        );
+    }
+
+    #[test]
+    fn test_amortise_with_observer() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        let spec = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            24,
+            disbursal_date,
+            first_payment_date,
+        )
+        .build();
+
+        let mut iterations = Vec::new();
+        let schedule = spec
+            .amortise_with_observer(&mut |i: &SolverIteration| iterations.push(*i))
+            .unwrap();
+
+        assert!(!iterations.is_empty());
+        for (n, iteration) in iterations.iter().enumerate() {
+            assert_eq!(iteration.iteration as usize, n + 1);
+        }
+        let last = iterations.last().unwrap();
+        assert!(last.residual.abs() < dec!(1));
+        assert_eq!(
+            round_decimal(last.estimate, None, None, None),
+            schedule.payments[0].payment
+        );
+
+        // Observing must not change the result
+        assert_eq!(
+            spec.amortise().unwrap().meta.total_payable,
+            schedule.meta.total_payable
+        );
+    }
+
+    #[test]
+    fn test_amortise_long_term_high_rate_balloon() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        let spec = LoanSpec::builder(
+            dec!(250000),
+            dec!(0.29),
+            360,
+            disbursal_date,
+            first_payment_date,
+        )
+        .balloon_payment(dec!(200000))
+        .build();
+
+        let schedule = spec.amortise().unwrap();
+
+        // A penny on the payment moves the final balance by around £1,000 here, so check the
+        // payment is within a penny of the root rather than the residual
+        let iteration_spec = spec.iteration_spec();
+        let payment = schedule.payments[0].payment;
+        let below = residual(&iteration_spec, payment - dec!(0.01), spec.balloon_payment).unwrap();
+        let above = residual(&iteration_spec, payment + dec!(0.01), spec.balloon_payment).unwrap();
+        assert!(
+            below > Decimal::ZERO && above < Decimal::ZERO,
+            "{} {}",
+            below,
+            above
+        );
+        assert!(schedule.meta.solver.unwrap().iterations <= spec.solver.max_iterations * 2);
+    }
+
+    #[test]
+    fn test_amortise_reports_solver() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        let builder = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            24,
+            disbursal_date,
+            first_payment_date,
+        );
+
+        let solved = builder.clone().build().amortise().unwrap();
+        assert!(solved.meta.solver.unwrap().residual.abs() < dec!(1));
+
+        let fixed = builder.fixed_payment(dec!(700)).build().amortise().unwrap();
+        assert_eq!(fixed.meta.solver, None);
+    }
+
+    #[test]
+    fn test_amortise_iteration_limit() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        let result = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            24,
+            disbursal_date,
+            first_payment_date,
+        )
+        .solver(SolverConfig {
+            tolerance: dec!(0.01),
+            max_iterations: 2,
+        })
+        .build()
+        .amortise();
+
+        assert!(matches!(
+            result,
+            Err(AmortisationError::NonConvergence { .. })
+        ));
+    }
+
+    #[test]
+    fn test_closed_form_payment_matches_solver() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+
+        for frequency in [
+            PaymentFrequency::Weekly,
+            PaymentFrequency::Monthly,
+            PaymentFrequency::Quarterly,
+        ] {
+            for (annual_rate, num_payments, balloon) in [
+                (dec!(0.05), 24, None),
+                (dec!(0.199), 60, Some(dec!(6000))),
+                (dec!(0.29), 360, Some(dec!(12000))),
+                (Decimal::ZERO, 12, None),
+            ] {
+                for interest_type in [InterestType::Simple, InterestType::Compound] {
+                    let mut builder = LoanSpec::builder(
+                        dec!(15000),
+                        annual_rate,
+                        num_payments,
+                        disbursal_date,
+                        first_payment_date,
+                    )
+                    .interest_method(InterestMethod::Convention30_360)
+                    .interest_type(interest_type)
+                    .frequency(frequency);
+                    if let Some(balloon) = balloon {
+                        builder = builder.balloon_payment(balloon);
+                    }
+                    let spec = builder.build();
+
+                    let closed_form = spec.closed_form_payment().unwrap().unwrap();
+                    let (solved, _) = spec.solve_payment(&mut NoopObserver).unwrap();
+
+                    assert!(
+                        (closed_form - solved).abs() <= dec!(0.01),
+                        "{:?} {} {:?}: closed form {} solved {}",
+                        frequency,
+                        annual_rate,
+                        balloon,
+                        closed_form,
+                        solved
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_form_payment_only_for_30_360() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        let builder = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            24,
+            disbursal_date,
+            first_payment_date,
+        );
+
+        assert_eq!(
+            builder
+                .clone()
+                .interest_method(InterestMethod::Actual365)
+                .build()
+                .closed_form_payment(),
+            Ok(None)
+        );
+
+        let schedule = builder
+            .interest_method(InterestMethod::Convention30_360)
+            .build()
+            .amortise()
+            .unwrap();
+        // 15000 * 0.05/12 / (1 - (1 + 0.05/12)^-24)
+        assert_eq!(schedule.payments[0].payment, dec!(658.07));
+        assert_eq!(schedule.meta.solver, None);
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_amortise_with_frequencies() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let mut previous_interest = Decimal::ZERO;
+
+        for (frequency, first_payment_date, num_payments) in [
+            (
+                PaymentFrequency::Weekly,
+                NaiveDate::from_ymd_opt(2023, 1, 8).unwrap(),
+                104,
+            ),
+            (
+                PaymentFrequency::Fortnightly,
+                NaiveDate::from_ymd_opt(2023, 1, 15).unwrap(),
+                52,
+            ),
+            (
+                PaymentFrequency::FourWeekly,
+                NaiveDate::from_ymd_opt(2023, 1, 29).unwrap(),
+                26,
+            ),
+            (
+                PaymentFrequency::Quarterly,
+                NaiveDate::from_ymd_opt(2023, 4, 1).unwrap(),
+                8,
+            ),
+            (
+                PaymentFrequency::SemiAnnual,
+                NaiveDate::from_ymd_opt(2023, 7, 1).unwrap(),
+                4,
+            ),
+            (
+                PaymentFrequency::Annual,
+                NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                2,
+            ),
+        ] {
+            let schedule = LoanSpec::builder(
+                dec!(15000),
+                dec!(0.06),
+                num_payments,
+                disbursal_date,
+                first_payment_date,
+            )
+            .frequency(frequency)
+            .build()
+            .amortise()
+            .unwrap();
+
+            assert_eq!(schedule.payments.len(), num_payments as usize);
+            assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+            assert_eq!(schedule.meta.total_principal, dec!(15000));
+            // Every schedule spans two years, so less frequent payments repay principal more
+            // slowly and accrue more interest
+            assert!(
+                schedule.meta.total_interest > previous_interest,
+                "{:?} total interest {}",
+                frequency,
+                schedule.meta.total_interest
+            );
+            previous_interest = schedule.meta.total_interest;
+        }
+    }
+
+    /// Actual/365 Fixed reimplemented outside the crate's interest methods.
+    #[derive(Debug)]
+    struct HouseActual365;
+
+    impl DayCounter for HouseActual365 {
+        fn days_in_year(&self) -> Decimal {
+            dec!(365)
+        }
+
+        fn accrued_days(
+            &self,
+            start: NaiveDate,
+            end: NaiveDate,
+            _period: &AccrualPeriod,
+        ) -> Decimal {
+            Decimal::from((end - start).num_days() + 1)
+        }
+    }
+
+    /// A flat 30 days every month over a 360-day year, like `Convention30_360`.
+    #[derive(Debug)]
+    struct HouseFlat30;
+
+    impl DayCounter for HouseFlat30 {
+        fn days_in_year(&self) -> Decimal {
+            dec!(360)
+        }
+
+        fn accrued_days(
+            &self,
+            _start: NaiveDate,
+            _end: NaiveDate,
+            _period: &AccrualPeriod,
+        ) -> Decimal {
+            unreachable!("every period is charged a fixed number of days")
+        }
+
+        fn fixed_period_days(&self, frequency: PaymentFrequency) -> Option<u32> {
+            Some(frequency.days_30_360())
+        }
+    }
+
+    #[test]
+    fn test_amortise_with_custom_day_counter() {
+        let builder = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.079),
+            36,
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+        );
+
+        // The custom counter takes precedence over the interest method
+        let custom = builder
+            .clone()
+            .interest_method(InterestMethod::Actual360)
+            .day_counter(HouseActual365)
+            .build()
+            .amortise()
+            .unwrap();
+        let built_in = builder
+            .clone()
+            .interest_method(InterestMethod::Actual365)
+            .build()
+            .amortise()
+            .unwrap();
+        assert_eq!(custom.payments[0].payment, built_in.payments[0].payment);
+        assert_eq!(custom.meta.total_interest, built_in.meta.total_interest);
+
+        // Fixed-period custom counters take the closed-form path, as 30/360 does
+        let custom = builder
+            .clone()
+            .day_counter(HouseFlat30)
+            .build()
+            .amortise()
+            .unwrap();
+        let built_in = builder
+            .interest_method(InterestMethod::Convention30_360)
+            .build()
+            .amortise()
+            .unwrap();
+        assert_eq!(custom.payments[0].payment, built_in.payments[0].payment);
+        assert_eq!(custom.meta.total_interest, built_in.meta.total_interest);
+        assert!(custom.meta.solver.is_none());
+    }
+
+    fn two_year_fix(reversion_rate: Decimal) -> LoanBuilder {
+        LoanSpec::builder(
+            dec!(150000),
+            dec!(0.04),
+            120,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Convention30_360)
+        .rate_change(NaiveDate::from_ymd_opt(2026, 1, 2).unwrap(), reversion_rate)
+    }
+
+    #[test]
+    fn test_amortise_rate_change_recast() {
+        let schedule = two_year_fix(dec!(0.07)).build().amortise().unwrap();
+        let fixed_rate = LoanSpec {
+            rate_changes: Vec::new(),
+            ..two_year_fix(dec!(0.07)).build()
+        }
+        .amortise()
+        .unwrap();
+
+        // The first 24 payments are set at the initial rate
+        let initial = fixed_rate.payments[0].payment;
+        assert!(schedule.payments[..24].iter().all(|p| p.payment == initial));
+        assert_eq!(
+            schedule.payments[23].balance,
+            fixed_rate.payments[23].balance
+        );
+
+        // Then the payment is recast to repay the balance over the last 96 at 7%
+        let reversion = schedule.payments[24].payment;
+        let expected = annuity_payment(
+            schedule.payments[23].balance,
+            Decimal::ZERO,
+            dec!(0.07) / dec!(12),
+            96,
+        )
+        .unwrap();
+        assert_eq!(reversion, expected);
+        assert!(reversion > initial);
+        assert!(schedule.payments[24..119]
+            .iter()
+            .all(|p| p.payment == reversion));
+        assert_eq!(schedule.payments.len(), 120);
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!((schedule.payments[119].payment - reversion).abs() < dec!(1));
+    }
+
+    #[test]
+    fn test_amortise_rate_change_flex_term() {
+        let schedule = two_year_fix(dec!(0.07))
+            .rate_change_payment(RateChangePayment::FlexTerm)
+            .build()
+            .amortise()
+            .unwrap();
+
+        // A higher rate keeps the payment and lengthens the term
+        let payment = schedule.payments[0].payment;
+        let term = schedule.payments.len();
+        assert!(term > 120, "term {}", term);
+        assert_eq!(schedule.meta.term as usize, term);
+        assert!(schedule.payments[..term - 1]
+            .iter()
+            .all(|p| p.payment == payment));
+        assert!(schedule.payments[term - 1].payment <= payment);
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+
+        // A lower one shortens it
+        let schedule = two_year_fix(dec!(0.02))
+            .rate_change_payment(RateChangePayment::FlexTerm)
+            .build()
+            .amortise()
+            .unwrap();
+        assert!(schedule.payments.len() < 120);
+        assert_eq!(schedule.payments[0].payment, payment);
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+
+        // Flexing the term can't leave a balloon payment
+        assert_eq!(
+            two_year_fix(dec!(0.07))
+                .rate_change_payment(RateChangePayment::FlexTerm)
+                .balloon_payment(dec!(50000))
+                .build()
+                .amortise()
+                .unwrap_err(),
+            AmortisationError::Unsupported("flexing the term of a loan with a balloon payment")
+        );
+    }
+
+    #[test]
+    fn test_amortise_rate_change_actual_days() {
+        // Date-based day counts recast approximately, and the final payment settles the rest
+        let schedule = two_year_fix(dec!(0.07))
+            .interest_method(InterestMethod::Actual365)
+            .build()
+            .amortise()
+            .unwrap();
+
+        let reversion = schedule.payments[24].payment;
+        assert!(reversion > schedule.payments[23].payment);
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!((schedule.payments[119].payment - reversion).abs() < dec!(50));
+    }
+
+    #[test]
+    fn test_amortise_floating_rate() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let index = RateIndex::new([
+            (date(2023, 12, 20), dec!(0.0519)),
+            (date(2024, 3, 20), dec!(0.0495)),
+            (date(2024, 6, 20), dec!(-0.0010)),
+        ]);
+        // The annual rate is ignored in favour of the index
+        let schedule = LoanSpec::builder(
+            dec!(100000),
+            Decimal::ZERO,
+            12,
+            date(2024, 1, 1),
+            date(2024, 2, 1),
+        )
+        .interest_method(InterestMethod::Convention30_360)
+        .floating_rate(FloatingRate {
+            lookback_days: 5,
+            floor: Some(Decimal::ZERO),
+            ..FloatingRate::new(index, dec!(0.03))
+        })
+        .build()
+        .amortise()
+        .unwrap();
+
+        assert_eq!(schedule.meta.annual_rate, dec!(0.0819));
+        let rates: Vec<_> = schedule.payments.iter().map(|p| p.annual_rate).collect();
+        assert_eq!(rates[..3], [dec!(0.0819); 3]);
+        // The April period observes the fixing of 26 March
+        assert_eq!(rates[3..6], [dec!(0.0795); 3]);
+        // A negative index is floored at zero, leaving the margin
+        assert_eq!(rates[6..], [dec!(0.03); 6]);
+
+        // The payment is recast at each change and the loan still repays in full
+        let payments = &schedule.payments;
+        assert_eq!(payments[0].payment, payments[2].payment);
+        assert_eq!(
+            payments[3].payment,
+            annuity_payment(
+                payments[2].balance,
+                Decimal::ZERO,
+                dec!(0.0795) / dec!(12),
+                9
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            payments[3].interest,
+            (payments[3].opening_balance * dec!(0.0795) / dec!(12)).round_dp(2)
+        );
+        assert!(payments[6].payment < payments[5].payment);
+        assert_eq!(payments.last().unwrap().balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_amortise_interest_only_periods() {
+        let schedule = LoanSpec::builder(
+            dec!(200000),
+            dec!(0.06),
+            36,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Convention30_360)
+        .interest_only_periods(12)
+        .build()
+        .amortise()
+        .unwrap();
+
+        // A year of interest-only payments leaves the balance untouched
+        for payment in &schedule.payments[..12] {
+            assert_eq!(payment.interest, dec!(1000));
+            assert_eq!(payment.payment, dec!(1000));
+            assert_eq!(payment.principal, Decimal::ZERO);
+            assert_eq!(payment.balance, dec!(200000));
+        }
+
+        // Then the full balance amortises over the remaining 24 payments
+        let expected = annuity_payment(dec!(200000), Decimal::ZERO, dec!(0.005), 24).unwrap();
+        assert!(schedule.payments[12..35]
+            .iter()
+            .all(|p| p.payment == expected));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!(schedule.meta.solver.is_none());
+    }
+
+    #[test]
+    fn test_amortise_interest_only_loan() {
+        let spec = LoanSpec::builder(
+            dec!(100000),
+            dec!(0.05),
+            12,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Actual365)
+        .interest_only_periods(12)
+        .build();
+        let schedule = spec.amortise().unwrap();
+
+        // Each payment is the period's interest, and the last one repays the principal too
+        for payment in &schedule.payments[..11] {
+            assert_eq!(payment.payment, payment.interest);
+            assert_eq!(payment.balance, dec!(100000));
+        }
+        let last = schedule.payments.last().unwrap();
+        assert_eq!(last.principal, dec!(100000));
+        assert_eq!(last.payment, dec!(100000) + last.interest);
+        assert_eq!(last.balance, Decimal::ZERO);
+        assert_eq!(schedule.meta.total_principal, dec!(100000));
+        assert!(schedule.meta.solver.is_none());
+
+        // There's no payment to solve for
+        assert_eq!(
+            spec.solve_rate().unwrap_err(),
+            AmortisationError::Unsupported("solving for a parameter of an interest-only loan")
+        );
+    }
+
+    #[test]
+    fn test_amortise_interest_only_periods_actual_days() {
+        let schedule = LoanSpec::builder(
+            dec!(50000),
+            dec!(0.07),
+            48,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 15).unwrap(),
+        )
+        .interest_method(InterestMethod::Actual365)
+        .interest_only_periods(6)
+        .build()
+        .amortise()
+        .unwrap();
+
+        // The solver only levels the amortising payments, whose interest varies with the days
+        assert!(schedule.meta.solver.is_some());
+        assert!(schedule.payments[..6]
+            .iter()
+            .all(|p| p.payment == p.interest && p.balance == dec!(50000)));
+        let level = schedule.payments[6].payment;
+        assert!(level > schedule.payments[5].payment);
+        assert!(schedule.payments[6..47].iter().all(|p| p.payment == level));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!((schedule.payments[47].payment - level).abs() < dec!(1));
+    }
+
+    #[test]
+    fn test_amortise_equal_principal() {
+        let schedule = LoanSpec::builder(
+            dec!(12000),
+            dec!(0.06),
+            12,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Convention30_360)
+        .amortisation_style(AmortisationStyle::EqualPrincipal)
+        .build()
+        .amortise()
+        .unwrap();
+
+        // £1,000 of principal each month, with a month's interest on what's left on top
+        for (i, payment) in schedule.payments.iter().enumerate() {
+            let interest = dec!(60) - dec!(5) * Decimal::from(i);
+            assert_eq!(payment.principal, dec!(1000));
+            assert_eq!(payment.interest, interest);
+            assert_eq!(payment.payment, dec!(1000) + interest);
+        }
+        assert_eq!(schedule.meta.total_interest, dec!(390));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!(schedule.meta.solver.is_none());
+    }
+
+    #[test]
+    fn test_amortise_equal_principal_with_balloon_and_option_fee() {
+        let spec = LoanSpec::builder(
+            dec!(10000),
+            dec!(0.079),
+            36,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 15).unwrap(),
+        )
+        .amortisation_style(AmortisationStyle::EqualPrincipal)
+        .balloon_payment(dec!(4000))
+        .option_fee(dec!(10))
+        .build();
+        let schedule = spec.amortise().unwrap();
+
+        // £6,000 repaid in 36 instalments, leaving the balloon for the last payment
+        assert_eq!(spec.principal_instalment(), dec!(166.67));
+        assert!(schedule.payments[..35]
+            .iter()
+            .all(|p| p.principal == dec!(166.67) && p.payment == p.principal + p.interest));
+        // Longer months can charge a little more interest, but the trend is down
+        assert!(schedule.payments[34].payment < schedule.payments[0].payment - dec!(30));
+        let last = schedule.payments.last().unwrap();
+        assert_eq!(last.opening_balance, dec!(4166.55));
+        assert_eq!(last.principal, dec!(4166.55));
+        assert_eq!(last.payment, dec!(4166.55) + last.interest + dec!(10));
+        assert_eq!(last.balance, Decimal::ZERO);
+
+        assert_eq!(
+            spec.solve_rate().unwrap_err(),
+            AmortisationError::Unsupported("solving for a parameter of an equal principal loan")
+        );
+    }
+
+    #[test]
+    fn test_amortise_stepped_payments() {
+        let schedule = LoanSpec::builder(
+            dec!(20000),
+            dec!(0.06),
+            36,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Convention30_360)
+        .payment_profile(PaymentProfile::Stepped {
+            every: 12,
+            rate: dec!(0.03),
+        })
+        .build()
+        .amortise()
+        .unwrap();
+
+        // Each year's payments are 3% up on the year before, and the base is found in closed
+        // form so that they repay the loan
+        let base = schedule.payments[0].payment;
+        let second = round_decimal(base * dec!(1.03), None, None, None);
+        let third = round_decimal(base * dec!(1.0609), None, None, None);
+        assert!(schedule.payments[..12].iter().all(|p| p.payment == base));
+        assert!(schedule.payments[12..24]
+            .iter()
+            .all(|p| p.payment == second));
+        assert!(schedule.payments[24..35].iter().all(|p| p.payment == third));
+        assert!(base < annuity_payment(dec!(20000), Decimal::ZERO, dec!(0.005), 36).unwrap());
+        let last = schedule.payments.last().unwrap();
+        assert_eq!(last.balance, Decimal::ZERO);
+        assert!((last.payment - third).abs() < dec!(0.05));
+        assert!(schedule.meta.solver.is_none());
+    }
+
+    #[test]
+    fn test_amortise_weighted_payments() {
+        // Half payments for the first six months, then full ones
+        let mut weights = vec![dec!(0.5); 6];
+        weights.push(dec!(1));
+        let spec = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.089),
+            36,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 15).unwrap(),
+        )
+        .interest_method(InterestMethod::Actual365)
+        .payment_profile(PaymentProfile::Weights(weights))
+        .build();
+        let schedule = spec.amortise().unwrap();
+
+        assert!(schedule.meta.solver.is_some());
+        let full = schedule.payments[6].payment;
+        let half = round_decimal(full * dec!(0.5), None, None, None);
+        assert!(schedule.payments[..6].iter().all(|p| p.payment == half));
+        assert!(schedule.payments[6..35].iter().all(|p| p.payment == full));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!((schedule.payments[35].payment - full).abs() < dec!(1));
+
+        // A fixed payment is the base the weights apply to
+        let fixed = LoanSpec {
+            fixed_payment: Some(dec!(500)),
+            ..spec
+        }
+        .amortise()
+        .unwrap();
+        assert_eq!(fixed.payments[0].payment, dec!(250));
+        assert_eq!(fixed.payments[6].payment, dec!(500));
+    }
+
+    #[test]
+    fn test_amortise_stepped_payments_recast() {
+        let schedule = two_year_fix(dec!(0.07))
+            .payment_profile(PaymentProfile::Stepped {
+                every: 12,
+                rate: dec!(0.03),
+            })
+            .build()
+            .amortise()
+            .unwrap();
+
+        // The reversion recasts the base payment, and the steps carry on from it
+        let reversion = schedule.payments[24].payment;
+        assert!(reversion > schedule.payments[23].payment * dec!(1.03));
+        assert!(schedule.payments[24..36]
+            .iter()
+            .all(|p| p.payment == reversion));
+        let stepped = schedule.payments[36].payment;
+        assert!((stepped / reversion - dec!(1.03)).abs() < dec!(0.0001));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!((schedule.payments[119].payment - schedule.payments[118].payment).abs() < dec!(1));
+    }
+
+    fn advance_rentals(advance_payments: u32, num_payments: u32) -> LoanSpec {
+        LoanSpec::builder(
+            dec!(20000),
+            dec!(0.06),
+            num_payments,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Convention30_360)
+        .advance_payments(advance_payments)
+        .build()
+    }
+
+    #[test]
+    fn test_amortise_advance_payments() {
+        let spec = advance_rentals(3, 35);
+        let schedule = spec.amortise().unwrap();
+
+        // A 3+35 profile: three payments' worth on the disbursal date, then 35 monthly
+        assert_eq!(schedule.payments.len(), 36);
+        assert_eq!(schedule.meta.term, 35);
+        let rental = schedule.payments[1].payment;
+        let advance = &schedule.payments[0];
+        assert_eq!(advance.month, 0);
+        assert_eq!(advance.due_date, spec.disbursal_date);
+        assert_eq!(advance.payment, rental * dec!(3));
+        assert_eq!(advance.principal, advance.payment);
+        assert_eq!(advance.interest, Decimal::ZERO);
+        assert_eq!(advance.days, 0);
+
+        // Interest runs on the balance left after the advance
+        let net_credit = dec!(20000) - advance.payment;
+        assert_eq!(advance.balance, net_credit);
+        assert_eq!(
+            schedule.payments[1].interest,
+            round_decimal(net_credit * dec!(0.005), None, None, None)
+        );
+        assert!(schedule.payments[1..35].iter().all(|p| p.payment == rental));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert_eq!(
+            schedule.meta.total_payable,
+            schedule.payments.iter().map(|p| p.payment).sum::<Decimal>()
+        );
+        assert!(schedule.meta.solver.is_none());
+
+        // The APR is that of lending only the net credit over the 35 payments
+        let net = LoanSpec {
+            principal: net_credit,
+            advance_payments: 0,
+            ..spec
+        }
+        .amortise()
+        .unwrap();
+        assert_eq!(net.payments[0].payment, rental);
+        assert_eq!(schedule.meta.calculated_apr, net.meta.calculated_apr);
+        assert_eq!(schedule.meta.total_interest, net.meta.total_interest);
+    }
+
+    #[test]
+    fn test_amortise_annuity_due() {
+        // Paying the first of 38 payments up front costs less than paying all 38 in arrears
+        let due = advance_rentals(1, 37).amortise().unwrap();
+        let arrears = advance_rentals(0, 38).amortise().unwrap();
+
+        assert_eq!(due.payments[0].payment, due.payments[1].payment);
+        assert!(due.payments[0].payment < arrears.payments[0].payment);
+        assert!(due.meta.total_interest < arrears.meta.total_interest);
+        assert_eq!(due.payments.last().unwrap().balance, Decimal::ZERO);
+
+        // Date-based day counts solve for the same profile
+        let solved = LoanSpec {
+            interest_method: InterestMethod::Actual365,
+            ..advance_rentals(1, 37)
+        }
+        .amortise()
+        .unwrap();
+        assert!(solved.meta.solver.is_some());
+        assert_eq!(solved.payments[0].payment, solved.payments[1].payment);
+        assert_eq!(solved.payments.last().unwrap().balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_solve_term_with_advance_payments() {
+        let schedule = LoanSpec {
+            fixed_payment: Some(dec!(600)),
+            ..advance_rentals(3, 0)
+        }
+        .solve_term()
+        .unwrap();
+
+        // The term counts the regular payments after the advance
+        assert_eq!(schedule.payments[0].payment, dec!(1800));
+        assert_eq!(schedule.payments.len() as u32, schedule.meta.term + 1);
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_amortise_motor_finance_deal() {
+        let deal = MotorFinanceDeal {
+            customer_deposit: dec!(2000),
+            part_exchange_value: dec!(6000),
+            part_exchange_settlement: dec!(4500),
+            dealer_deposit_contribution: dec!(750),
+            ..MotorFinanceDeal::new(dec!(25000))
+        };
+        let spec = LoanSpec::builder(
+            Decimal::ZERO,
+            dec!(0.089),
+            36,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .deal(deal.clone())
+        .balloon_payment(dec!(9000))
+        .option_fee(dec!(10))
+        .build();
+        assert_eq!(spec.principal, dec!(20750));
+
+        let schedule = spec.amortise().unwrap();
+        let meta = &schedule.meta;
+        assert_eq!(meta.amount_of_credit, dec!(20750));
+        assert_eq!(meta.total_deposit, dec!(3500));
+        assert_eq!(meta.dealer_deposit_contribution, dec!(750));
+        assert_eq!(
+            meta.total_charge_for_credit,
+            meta.total_payable - dec!(20750)
+        );
+        // The customer pays their deposit, the net part-exchange and the payments, but not the
+        // dealer's contribution
+        assert_eq!(
+            meta.total_amount_payable,
+            dec!(2000) + dec!(1500) + meta.total_payable
+        );
+        assert_eq!(
+            meta.total_amount_payable,
+            dec!(25000) - dec!(750) + meta.total_charge_for_credit
+        );
+        assert_eq!(meta.deal, Some(deal));
+
+        assert_eq!(
+            LoanSpec {
+                principal: dec!(21000),
+                ..spec.clone()
+            }
+            .amortise()
+            .unwrap_err(),
+            AmortisationError::DealCreditMismatch {
+                principal: dec!(21000),
+                amount_of_credit: dec!(20750),
+            }
+        );
+        assert_eq!(
+            LoanSpec {
+                fixed_payment: Some(dec!(400)),
+                ..spec
+            }
+            .solve_principal()
+            .unwrap_err(),
+            AmortisationError::Unsupported("solving for the principal of a motor finance deal")
+        );
+    }
+
+    #[test]
+    fn test_amortise_compounded_rfr() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // SONIA falling by a basis point each business day through the first quarter of 2024,
+        // then held flat at the last fixing
+        let calendar = Calendar::EnglandAndWales;
+        let mut fixing = dec!(0.0530);
+        let mut fixings = Vec::new();
+        let mut day = date(2023, 12, 1);
+        while day <= date(2024, 3, 28) {
+            if calendar.is_business_day(day) {
+                fixings.push((day, fixing));
+                fixing -= dec!(0.0001);
+            }
+            day = day.succ_opt().unwrap();
+        }
+        let last_fixing = fixings.last().unwrap().1;
+        let rfr = CompoundedRfr {
+            lookback_days: 5,
+            calendar,
+            ..CompoundedRfr::new(RateIndex::new(fixings), dec!(0.025))
+        };
+
+        let spec = LoanSpec::builder(
+            dec!(500000),
+            Decimal::ZERO,
+            6,
+            date(2024, 1, 2),
+            date(2024, 2, 1),
+        )
+        .interest_method(InterestMethod::Actual365)
+        .compounded_rfr(rfr.clone())
+        .build();
+        let schedule = spec.amortise().unwrap();
+
+        // The first day looks back five business days, over Christmas and New Year, to 21
+        // December
+        assert_eq!(rfr.first_fixing_date(date(2024, 1, 2)), date(2023, 12, 21));
+        assert_eq!(schedule.meta.annual_rate, dec!(0.0516) + dec!(0.025));
+        for payment in &schedule.payments[..5] {
+            let compounded_rate = rfr
+                .compounded_rate(payment.accrual_start, payment.accrual_end, dec!(365))
+                .unwrap();
+            assert_eq!(payment.annual_rate, compounded_rate + dec!(0.025));
+            // As for any loan, the payment reduces the balance from its due date, the last day
+            assert_eq!(payment.due_date, payment.accrual_end);
+            let balance_days =
+                payment.opening_balance * Decimal::from(payment.days) - payment.payment;
+            assert_eq!(
+                payment.interest,
+                (balance_days * payment.annual_rate / dec!(365))
+                    .round_dp_with_strategy(2, RoundingStrategy::MidpointNearestEven)
+            );
+        }
+        // Falling fixings bring the rate down until they run out in March,
+        assert!(schedule.payments[1].annual_rate < schedule.payments[0].annual_rate);
+        // after which compounding a flat rate adds only a little to it
+        let flat_rate = last_fixing + dec!(0.025);
+        assert!(schedule.payments[4..]
+            .iter()
+            .all(|p| p.annual_rate > flat_rate && p.annual_rate - flat_rate < dec!(0.0001)));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+        assert!(schedule.meta.solver.is_some());
+
+        // Fixed-days day counts can't compound daily fixings
+        assert_eq!(
+            LoanSpec {
+                interest_method: InterestMethod::Convention30_360,
+                ..spec
+            }
+            .amortise()
+            .unwrap_err(),
+            AmortisationError::Unsupported("compounded RFR interest with a fixed-days day count")
+        );
+    }
 }
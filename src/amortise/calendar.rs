@@ -0,0 +1,546 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::error::AmortisationError;
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Decides which days are bank holidays. Weekends are never business days.
+pub trait HolidayCalendar {
+    fn is_holiday(&self, date: NaiveDate) -> bool;
+
+    fn is_business_day(&self, date: NaiveDate) -> bool {
+        !is_weekend(date) && !self.is_holiday(date)
+    }
+
+    /// The business day `days` business days before `date`, or `date` itself for zero.
+    fn business_days_before(&self, mut date: NaiveDate, days: u32) -> NaiveDate {
+        for _ in 0..days {
+            date = date - Days::new(1);
+            while !self.is_business_day(date) {
+                date = date - Days::new(1);
+            }
+        }
+        date
+    }
+}
+
+/// The holiday calendar payment dates are rolled around.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum Calendar {
+    /// No holidays, only weekends.
+    #[default]
+    WeekendsOnly,
+    /// England and Wales bank holidays.
+    EnglandAndWales,
+    /// US federal holidays, observed on the Friday or Monday when they fall at a weekend.
+    UsFederal,
+    /// TARGET2 closing days, for euro payments.
+    Target2,
+    /// A house list of holiday dates.
+    Custom(HolidayList),
+}
+
+impl FromStr for Calendar {
+    type Err = AmortisationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "WeekendsOnly" => Ok(Calendar::WeekendsOnly),
+            "EnglandAndWales" => Ok(Calendar::EnglandAndWales),
+            "UsFederal" => Ok(Calendar::UsFederal),
+            "Target2" => Ok(Calendar::Target2),
+            _ => Err(AmortisationError::UnknownCalendar(s.to_string())),
+        }
+    }
+}
+
+impl HolidayCalendar for Calendar {
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        match self {
+            Calendar::WeekendsOnly => false,
+            Calendar::EnglandAndWales => HolidayRules::EnglandAndWales.contains(date.year(), date),
+            // New Year's Day falling on a Saturday is observed on 31 December
+            Calendar::UsFederal => {
+                HolidayRules::UsFederal.contains(date.year(), date)
+                    || HolidayRules::UsFederal.contains(date.year() + 1, date)
+            }
+            Calendar::Target2 => HolidayRules::Target2.contains(date.year(), date),
+            Calendar::Custom(holidays) => holidays.is_holiday(date),
+        }
+    }
+}
+
+/// Holiday dates read from a file or a spec, one ISO date (YYYY-MM-DD) per line. Blank
+/// lines and anything after `#` are ignored.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HolidayList(BTreeSet<NaiveDate>);
+
+impl HolidayList {
+    pub fn new(dates: impl IntoIterator<Item = NaiveDate>) -> Self {
+        HolidayList(dates.into_iter().collect())
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, AmortisationError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| AmortisationError::HolidayFile {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        contents.parse()
+    }
+}
+
+impl FromStr for HolidayList {
+    type Err = AmortisationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                NaiveDate::parse_from_str(line, "%Y-%m-%d")
+                    .map_err(|_| AmortisationError::InvalidHolidayDate(line.to_string()))
+            })
+            .collect::<Result<_, _>>()
+            .map(HolidayList)
+    }
+}
+
+impl HolidayCalendar for HolidayList {
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.0.contains(&date)
+    }
+}
+
+/// How a date falling on a weekend or holiday is moved to a business day.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum BusinessDayConvention {
+    /// Dates are left where they fall.
+    #[default]
+    Unadjusted,
+    /// The next business day.
+    Following,
+    /// The next business day, unless that is in the next month, then the previous one.
+    ModifiedFollowing,
+    /// The previous business day.
+    Preceding,
+}
+
+impl FromStr for BusinessDayConvention {
+    type Err = AmortisationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Unadjusted" => Ok(BusinessDayConvention::Unadjusted),
+            "Following" => Ok(BusinessDayConvention::Following),
+            "ModifiedFollowing" => Ok(BusinessDayConvention::ModifiedFollowing),
+            "Preceding" => Ok(BusinessDayConvention::Preceding),
+            _ => Err(AmortisationError::UnknownBusinessDayConvention(
+                s.to_string(),
+            )),
+        }
+    }
+}
+
+impl BusinessDayConvention {
+    /// Moves `date` to a business day in `calendar`.
+    pub fn adjust(&self, date: NaiveDate, calendar: &dyn HolidayCalendar) -> NaiveDate {
+        match self {
+            BusinessDayConvention::Unadjusted => date,
+            BusinessDayConvention::Following => following(date, calendar),
+            BusinessDayConvention::ModifiedFollowing => {
+                let adjusted = following(date, calendar);
+                if adjusted.month() == date.month() {
+                    adjusted
+                } else {
+                    preceding(date, calendar)
+                }
+            }
+            BusinessDayConvention::Preceding => preceding(date, calendar),
+        }
+    }
+}
+
+fn following(mut date: NaiveDate, calendar: &dyn HolidayCalendar) -> NaiveDate {
+    while !calendar.is_business_day(date) {
+        date = date + Days::new(1);
+    }
+    date
+}
+
+fn preceding(mut date: NaiveDate, calendar: &dyn HolidayCalendar) -> NaiveDate {
+    while !calendar.is_business_day(date) {
+        date = date - Days::new(1);
+    }
+    date
+}
+
+fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+/// Easter Sunday in the Gregorian calendar (the anonymous Gregorian algorithm).
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    ymd(year, month as u32, day as u32)
+}
+
+/// The `n`th `weekday` of the month, counting from 1.
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).unwrap()
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    let mut date = NaiveDate::from_weekday_of_month_opt(year, month, weekday, 4).unwrap();
+    while let Some(next) = date
+        .checked_add_days(Days::new(7))
+        .filter(|d| d.month() == month)
+    {
+        date = next;
+    }
+    date
+}
+
+/// A weekend holiday moved to the following Monday.
+fn substitute_monday(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date + Days::new(2),
+        Weekday::Sun => date + Days::new(1),
+        _ => date,
+    }
+}
+
+/// A weekend holiday observed on the nearest weekday, as for US federal holidays.
+fn observed_nearest(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Days::new(1),
+        Weekday::Sun => date + Days::new(1),
+        _ => date,
+    }
+}
+
+/// The rules of the built-in calendars.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+enum HolidayRules {
+    EnglandAndWales,
+    UsFederal,
+    Target2,
+}
+
+impl HolidayRules {
+    fn holidays(self, year: i32) -> Vec<NaiveDate> {
+        match self {
+            HolidayRules::EnglandAndWales => england_and_wales_holidays(year),
+            HolidayRules::UsFederal => us_federal_holidays(year),
+            HolidayRules::Target2 => target2_holidays(year),
+        }
+    }
+
+    /// Whether `date` is one of `year`'s holidays. Each year's holidays are worked out the
+    /// first time they're needed and kept for the thread, as schedules look up every day.
+    fn contains(self, year: i32, date: NaiveDate) -> bool {
+        thread_local! {
+            static HOLIDAYS: RefCell<HashMap<(HolidayRules, i32), HashSet<NaiveDate>>> =
+                RefCell::new(HashMap::new());
+        }
+        HOLIDAYS.with(|holidays| {
+            holidays
+                .borrow_mut()
+                .entry((self, year))
+                .or_insert_with(|| self.holidays(year).into_iter().collect())
+                .contains(&date)
+        })
+    }
+}
+
+fn england_and_wales_holidays(year: i32) -> Vec<NaiveDate> {
+    let easter = easter_sunday(year);
+    let mut holidays = vec![
+        substitute_monday(ymd(year, 1, 1)),
+        easter - Days::new(2),
+        easter + Days::new(1),
+        last_weekday(year, 8, Weekday::Mon),
+    ];
+
+    // Early May bank holiday, moved for VE Day anniversaries
+    holidays.push(match year {
+        1995 | 2020 => ymd(year, 5, 8),
+        _ => nth_weekday(year, 5, Weekday::Mon, 1),
+    });
+    // Spring bank holiday, moved for jubilees
+    holidays.push(match year {
+        2002 | 2012 => ymd(year, 6, 4),
+        2022 => ymd(year, 6, 2),
+        _ => last_weekday(year, 5, Weekday::Mon),
+    });
+
+    // Christmas and Boxing Day, with substitute days when either falls at a weekend
+    let christmas = ymd(year, 12, 25);
+    holidays.extend(match christmas.weekday() {
+        Weekday::Fri => [christmas, ymd(year, 12, 28)],
+        Weekday::Sat => [ymd(year, 12, 27), ymd(year, 12, 28)],
+        Weekday::Sun => [ymd(year, 12, 26), ymd(year, 12, 27)],
+        _ => [christmas, ymd(year, 12, 26)],
+    });
+
+    // One-off bank holidays
+    holidays.extend(
+        [
+            ymd(1999, 12, 31),
+            ymd(2002, 6, 3),
+            ymd(2011, 4, 29),
+            ymd(2012, 6, 5),
+            ymd(2022, 6, 3),
+            ymd(2022, 9, 19),
+            ymd(2023, 5, 8),
+        ]
+        .into_iter()
+        .filter(|date| date.year() == year),
+    );
+    holidays
+}
+
+fn us_federal_holidays(year: i32) -> Vec<NaiveDate> {
+    let mut holidays = vec![
+        observed_nearest(ymd(year, 1, 1)),
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        last_weekday(year, 5, Weekday::Mon),
+        observed_nearest(ymd(year, 7, 4)),
+        nth_weekday(year, 9, Weekday::Mon, 1),
+        nth_weekday(year, 10, Weekday::Mon, 2),
+        observed_nearest(ymd(year, 11, 11)),
+        nth_weekday(year, 11, Weekday::Thu, 4),
+        observed_nearest(ymd(year, 12, 25)),
+    ];
+    if year >= 1986 {
+        holidays.push(nth_weekday(year, 1, Weekday::Mon, 3));
+    }
+    if year >= 2021 {
+        holidays.push(observed_nearest(ymd(year, 6, 19)));
+    }
+    holidays
+}
+
+fn target2_holidays(year: i32) -> Vec<NaiveDate> {
+    let easter = easter_sunday(year);
+    vec![
+        ymd(year, 1, 1),
+        easter - Days::new(2),
+        easter + Days::new(1),
+        ymd(year, 5, 1),
+        ymd(year, 12, 25),
+        ymd(year, 12, 26),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holidays_in(calendar: &Calendar, year: i32) -> Vec<NaiveDate> {
+        ymd(year, 1, 1)
+            .iter_days()
+            .take_while(|date| date.year() == year)
+            .filter(|date| calendar.is_holiday(*date))
+            .collect()
+    }
+
+    #[test]
+    fn test_easter_sunday() {
+        assert_eq!(easter_sunday(2019), ymd(2019, 4, 21));
+        assert_eq!(easter_sunday(2024), ymd(2024, 3, 31));
+        assert_eq!(easter_sunday(2025), ymd(2025, 4, 20));
+        assert_eq!(easter_sunday(2038), ymd(2038, 4, 25));
+    }
+
+    #[test]
+    fn test_england_and_wales() {
+        assert_eq!(
+            holidays_in(&Calendar::EnglandAndWales, 2024),
+            vec![
+                ymd(2024, 1, 1),
+                ymd(2024, 3, 29),
+                ymd(2024, 4, 1),
+                ymd(2024, 5, 6),
+                ymd(2024, 5, 27),
+                ymd(2024, 8, 26),
+                ymd(2024, 12, 25),
+                ymd(2024, 12, 26),
+            ]
+        );
+        // Substitute days, the Platinum Jubilee and the state funeral
+        assert_eq!(
+            holidays_in(&Calendar::EnglandAndWales, 2022),
+            vec![
+                ymd(2022, 1, 3),
+                ymd(2022, 4, 15),
+                ymd(2022, 4, 18),
+                ymd(2022, 5, 2),
+                ymd(2022, 6, 2),
+                ymd(2022, 6, 3),
+                ymd(2022, 8, 29),
+                ymd(2022, 9, 19),
+                ymd(2022, 12, 26),
+                ymd(2022, 12, 27),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_us_federal() {
+        // New Year's Day 2022 fell on a Saturday and was observed in 2021
+        assert!(Calendar::UsFederal.is_holiday(ymd(2021, 12, 31)));
+        assert_eq!(
+            holidays_in(&Calendar::UsFederal, 2022),
+            vec![
+                ymd(2022, 1, 17),
+                ymd(2022, 2, 21),
+                ymd(2022, 5, 30),
+                ymd(2022, 6, 20),
+                ymd(2022, 7, 4),
+                ymd(2022, 9, 5),
+                ymd(2022, 10, 10),
+                ymd(2022, 11, 11),
+                ymd(2022, 11, 24),
+                ymd(2022, 12, 26),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target2() {
+        assert_eq!(
+            holidays_in(&Calendar::Target2, 2024),
+            vec![
+                ymd(2024, 1, 1),
+                ymd(2024, 3, 29),
+                ymd(2024, 4, 1),
+                ymd(2024, 5, 1),
+                ymd(2024, 12, 25),
+                ymd(2024, 12, 26),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_business_day_conventions() {
+        let calendar = Calendar::EnglandAndWales;
+
+        // Good Friday 2024, followed by the Easter weekend and Easter Monday
+        let good_friday = ymd(2024, 3, 29);
+        assert_eq!(
+            BusinessDayConvention::Unadjusted.adjust(good_friday, &calendar),
+            good_friday
+        );
+        assert_eq!(
+            BusinessDayConvention::Following.adjust(good_friday, &calendar),
+            ymd(2024, 4, 2)
+        );
+        assert_eq!(
+            BusinessDayConvention::ModifiedFollowing.adjust(good_friday, &calendar),
+            ymd(2024, 3, 28)
+        );
+        assert_eq!(
+            BusinessDayConvention::Preceding.adjust(good_friday, &calendar),
+            ymd(2024, 3, 28)
+        );
+
+        // Saturday 1 June rolls forward within the month
+        assert_eq!(
+            BusinessDayConvention::ModifiedFollowing.adjust(ymd(2024, 6, 1), &calendar),
+            ymd(2024, 6, 3)
+        );
+        // A business day is left alone
+        assert_eq!(
+            BusinessDayConvention::Following.adjust(ymd(2024, 6, 3), &calendar),
+            ymd(2024, 6, 3)
+        );
+    }
+
+    #[test]
+    fn test_business_days_before() {
+        let calendar = Calendar::EnglandAndWales;
+
+        // Five business days before Wednesday 3 April 2024 skips the Easter weekend
+        assert_eq!(
+            calendar.business_days_before(ymd(2024, 4, 3), 5),
+            ymd(2024, 3, 25)
+        );
+        assert_eq!(
+            calendar.business_days_before(ymd(2024, 4, 3), 1),
+            ymd(2024, 4, 2)
+        );
+        assert_eq!(
+            calendar.business_days_before(ymd(2024, 4, 6), 0),
+            ymd(2024, 4, 6)
+        );
+    }
+
+    #[test]
+    fn test_holiday_list() {
+        let holidays: HolidayList =
+            "# Company closures\n2024-12-24\n\n2024-12-31  # New Year's Eve\n"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            holidays,
+            HolidayList::new([ymd(2024, 12, 24), ymd(2024, 12, 31)])
+        );
+        assert_eq!(
+            BusinessDayConvention::Following.adjust(ymd(2024, 12, 24), &holidays),
+            ymd(2024, 12, 25)
+        );
+        assert_eq!(
+            "2024-12-24\n24/12/2024".parse::<HolidayList>(),
+            Err(AmortisationError::InvalidHolidayDate(
+                "24/12/2024".to_string()
+            ))
+        );
+        assert!(matches!(
+            HolidayList::from_file("does/not/exist.txt"),
+            Err(AmortisationError::HolidayFile { .. })
+        ));
+    }
+
+    #[test]
+    fn test_calendar_from_json() {
+        let calendar: Calendar =
+            serde_json::from_str(r#"{"Custom": ["2024-12-24", "2024-12-31"]}"#).unwrap();
+
+        assert_eq!(
+            calendar,
+            Calendar::Custom(HolidayList::new([ymd(2024, 12, 24), ymd(2024, 12, 31)]))
+        );
+        assert_eq!(
+            serde_json::from_str::<Calendar>(r#""UsFederal""#).unwrap(),
+            Calendar::UsFederal
+        );
+        assert_eq!(
+            Calendar::from_str("Narnia"),
+            Err(AmortisationError::UnknownCalendar("Narnia".to_string()))
+        );
+    }
+}
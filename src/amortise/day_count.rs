@@ -0,0 +1,449 @@
+use super::calendar::{Calendar, HolidayCalendar};
+use super::frequency::PaymentFrequency;
+use super::interest::InterestMethod;
+use chrono::{Datelike, Days, NaiveDate};
+use rust_decimal::Decimal;
+use std::fmt;
+use std::sync::Arc;
+
+/// The dates one period's interest accrues over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccrualPeriod<'a> {
+    /// First day of accrual.
+    pub start: NaiveDate,
+    /// Last day of accrual, the capitalisation date.
+    pub end: NaiveDate,
+    /// The balance is reduced by the payment from this day.
+    pub payment_date: NaiveDate,
+    /// The loan's final capitalisation date, for day counts with termination date rules.
+    pub maturity: NaiveDate,
+    pub frequency: PaymentFrequency,
+    /// The loan's holiday calendar, for day counts over business days.
+    pub calendar: &'a Calendar,
+}
+
+/// A day count convention: how much interest a run of days earns.
+///
+/// Interest on a balance held from `start` to `end` is the annual rate divided by
+/// [`days_in_year`](DayCounter::days_in_year), times the balance, times
+/// [`accrued_days`](DayCounter::accrued_days). [`InterestMethod`] implements this for the
+/// built-in conventions; house conventions can implement it and be set on a loan with
+/// [`LoanBuilder::day_counter`](super::LoanBuilder::day_counter).
+pub trait DayCounter: fmt::Debug + Send + Sync {
+    /// The annual rate is divided by this to give the daily rate.
+    fn days_in_year(&self) -> Decimal;
+
+    /// Days charged at the daily rate for accrual from `start` to `end`, both inclusive, within
+    /// `period`. Fractional where some days earn a different share of the annual rate.
+    fn accrued_days(&self, start: NaiveDate, end: NaiveDate, period: &AccrualPeriod) -> Decimal;
+
+    /// Days reported on the payment for the same accrual. Defaults to calendar days.
+    fn counted_days(&self, start: NaiveDate, end: NaiveDate, _period: &AccrualPeriod) -> u32 {
+        calendar_days(start, end)
+    }
+
+    /// For conventions that charge every period the same number of days whatever the dates,
+    /// that number. Interest is then charged on the period's opening balance, and payments
+    /// can be found in closed form.
+    fn fixed_period_days(&self, _frequency: PaymentFrequency) -> Option<u32> {
+        None
+    }
+}
+
+/// A custom [`DayCounter`] shared between copies of a loan. Two are equal only if they are the
+/// same instance.
+#[derive(Debug, Clone)]
+pub struct CustomDayCounter(pub Arc<dyn DayCounter>);
+
+impl PartialEq for CustomDayCounter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl DayCounter for InterestMethod {
+    fn days_in_year(&self) -> Decimal {
+        match self {
+            InterestMethod::Convention30_360
+            | InterestMethod::Convention30_360Us
+            | InterestMethod::Convention30E360
+            | InterestMethod::Convention30E360Isda
+            | InterestMethod::Convention30_360German
+            | InterestMethod::Actual360 => Decimal::from(360),
+            InterestMethod::Actual364 => Decimal::from(364),
+            InterestMethod::Business252 => Decimal::from(252),
+            // Nominal for the Actual/Actual methods, adjusted per day in accrued_days
+            InterestMethod::Actual365
+            | InterestMethod::Actual365Leap
+            | InterestMethod::NoLeap365
+            | InterestMethod::ActualActualIsda
+            | InterestMethod::ActualActualIcma => Decimal::from(365),
+        }
+    }
+
+    fn accrued_days(&self, start: NaiveDate, end: NaiveDate, period: &AccrualPeriod) -> Decimal {
+        match self {
+            InterestMethod::ActualActualIsda => {
+                // Split by year, charging leap-year days at 1/366
+                let mut accrued = Decimal::ZERO;
+                let mut from = start;
+                while from <= end {
+                    let to = end.min(year_end(from));
+                    let days = calendar_days(from, to);
+                    accrued += if from.leap_year() {
+                        Decimal::from(days * 365) / Decimal::from(366)
+                    } else {
+                        Decimal::from(days)
+                    };
+                    from = to + Days::new(1);
+                }
+                accrued
+            }
+            InterestMethod::ActualActualIcma => {
+                // Each reference period earns 1/frequency of the annual rate
+                let mut accrued = Decimal::ZERO;
+                let mut from = start;
+                while from <= end {
+                    let (reference_start, reference_end) =
+                        icma_reference_period(from, period.end, period.frequency);
+                    let to = end.min(reference_end);
+                    let reference_days = (reference_end - reference_start).num_days() as u32;
+                    accrued += Decimal::from(calendar_days(from, to) * 365)
+                        / Decimal::from(reference_days * period.frequency.periods_per_year());
+                    from = to + Days::new(1);
+                }
+                accrued
+            }
+            InterestMethod::Actual365Leap if is_365_leap_period(period) => {
+                Decimal::from(calendar_days(start, end) * 365) / Decimal::from(366)
+            }
+            _ => Decimal::from(self.counted_days(start, end, period)),
+        }
+    }
+
+    fn counted_days(&self, start: NaiveDate, end: NaiveDate, period: &AccrualPeriod) -> u32 {
+        match self {
+            InterestMethod::Convention30_360Us
+            | InterestMethod::Convention30E360
+            | InterestMethod::Convention30E360Isda
+            | InterestMethod::Convention30_360German => {
+                // Counted from the day before accrual starts, as accrual includes both ends.
+                // 30/360 counts don't add up across a split at the 31st, so take the
+                // difference of running totals from the start of the period.
+                let anchor = period.start - Days::new(1);
+                let count_to = |date| days_30_360(anchor, date, period.maturity, *self);
+                count_to(end).saturating_sub(count_to(start - Days::new(1)))
+            }
+            InterestMethod::NoLeap365 => calendar_days(start, end) - leap_days(start, end),
+            InterestMethod::Business252 => business_days(start, end, period.calendar),
+            _ => calendar_days(start, end),
+        }
+    }
+
+    fn fixed_period_days(&self, frequency: PaymentFrequency) -> Option<u32> {
+        (*self == InterestMethod::Convention30_360).then(|| frequency.days_30_360())
+    }
+}
+
+/// Days from `start` to `end`, both inclusive.
+fn calendar_days(start: NaiveDate, end: NaiveDate) -> u32 {
+    (end - start).num_days() as u32 + 1
+}
+
+/// Number of 29 Februaries from `start` to `end`, both inclusive.
+fn leap_days(start: NaiveDate, end: NaiveDate) -> u32 {
+    (start.year()..=end.year())
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, 2, 29))
+        .filter(|date| *date >= start && *date <= end)
+        .count() as u32
+}
+
+/// Business days in `calendar` from `start` to `end`, both inclusive.
+fn business_days(start: NaiveDate, end: NaiveDate, calendar: &Calendar) -> u32 {
+    start
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .filter(|date| calendar.is_business_day(*date))
+        .count() as u32
+}
+
+/// Whether an Actual/365L period uses a 366-day year: when it contains 29 February for
+/// annual payments, otherwise when it ends in a leap year.
+fn is_365_leap_period(period: &AccrualPeriod) -> bool {
+    if period.frequency == PaymentFrequency::Annual {
+        leap_days(period.start, period.end) > 0
+    } else {
+        period.end.leap_year()
+    }
+}
+
+/// 30/360 day count from `start` to `end` under one of the date-based 30/360 conventions.
+fn days_30_360(
+    start: NaiveDate,
+    end: NaiveDate,
+    maturity: NaiveDate,
+    interest_method: InterestMethod,
+) -> u32 {
+    let mut d1 = start.day() as i64;
+    let mut d2 = end.day() as i64;
+
+    match interest_method {
+        InterestMethod::Convention30_360Us => {
+            if is_end_of_february(start) && is_end_of_february(end) {
+                d2 = 30;
+            }
+            if is_end_of_february(start) {
+                d1 = 30;
+            }
+            if d2 == 31 && d1 >= 30 {
+                d2 = 30;
+            }
+            if d1 == 31 {
+                d1 = 30;
+            }
+        }
+        InterestMethod::Convention30E360 => {
+            d1 = d1.min(30);
+            d2 = d2.min(30);
+        }
+        InterestMethod::Convention30E360Isda => {
+            if is_end_of_month(start) {
+                d1 = 30;
+            }
+            if is_end_of_month(end) && !(end == maturity && end.month() == 2) {
+                d2 = 30;
+            }
+        }
+        InterestMethod::Convention30_360German => {
+            if is_end_of_month(start) {
+                d1 = 30;
+            }
+            if is_end_of_month(end) {
+                d2 = 30;
+            }
+        }
+        _ => unreachable!("not a date-based 30/360 convention"),
+    }
+
+    let days = 360 * (end.year() - start.year()) as i64
+        + 30 * (end.month() as i64 - start.month() as i64)
+        + (d2 - d1);
+    days.max(0) as u32
+}
+
+fn is_end_of_month(date: NaiveDate) -> bool {
+    date.succ_opt()
+        .is_none_or(|next| next.month() != date.month())
+}
+
+fn is_end_of_february(date: NaiveDate) -> bool {
+    date.month() == 2 && is_end_of_month(date)
+}
+
+fn year_end(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), 12, 31).unwrap()
+}
+
+/// The ICMA reference period containing `date`, counting whole periods back from `period_end`.
+/// Returned as the exclusive start and inclusive end dates.
+fn icma_reference_period(
+    date: NaiveDate,
+    period_end: NaiveDate,
+    frequency: PaymentFrequency,
+) -> (NaiveDate, NaiveDate) {
+    let mut periods = 0;
+    loop {
+        let reference_end = frequency.previous_date(period_end, periods);
+        let reference_start = frequency.previous_date(period_end, periods + 1);
+        if date > reference_start {
+            return (reference_start, reference_end);
+        }
+        periods += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static WEEKENDS_ONLY: Calendar = Calendar::WeekendsOnly;
+
+    fn monthly_period(start: NaiveDate, end: NaiveDate) -> AccrualPeriod<'static> {
+        AccrualPeriod {
+            start,
+            end,
+            payment_date: end,
+            maturity: end,
+            frequency: PaymentFrequency::Monthly,
+            calendar: &WEEKENDS_ONLY,
+        }
+    }
+
+    #[test]
+    fn test_icma_reference_period() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(
+            icma_reference_period(
+                date(2024, 2, 10),
+                date(2024, 2, 29),
+                PaymentFrequency::Monthly
+            ),
+            (date(2024, 1, 29), date(2024, 2, 29))
+        );
+        assert_eq!(
+            icma_reference_period(
+                date(2024, 1, 29),
+                date(2024, 2, 29),
+                PaymentFrequency::Monthly
+            ),
+            (date(2023, 12, 29), date(2024, 1, 29))
+        );
+        assert_eq!(
+            icma_reference_period(
+                date(2024, 2, 10),
+                date(2024, 3, 31),
+                PaymentFrequency::Quarterly
+            ),
+            (date(2023, 12, 31), date(2024, 3, 31))
+        );
+    }
+
+    #[test]
+    fn test_days_30_360() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let later = date(2030, 1, 1);
+
+        // (start, end, maturity, [US, 30E, 30E ISDA, German])
+        let cases = [
+            (date(2023, 1, 16), date(2023, 2, 1), later, [15, 15, 15, 15]),
+            (
+                date(2024, 2, 29),
+                date(2024, 3, 31),
+                later,
+                [30, 31, 30, 30],
+            ),
+            (
+                date(2023, 2, 28),
+                date(2023, 8, 31),
+                later,
+                [180, 182, 180, 180],
+            ),
+            (
+                date(2024, 1, 31),
+                date(2024, 2, 29),
+                later,
+                [29, 29, 30, 30],
+            ),
+            // The end of February isn't extended when it's the final date under 30E/360 ISDA
+            (
+                date(2024, 1, 31),
+                date(2024, 2, 29),
+                date(2024, 2, 29),
+                [29, 29, 29, 30],
+            ),
+            (
+                date(2023, 3, 30),
+                date(2023, 5, 31),
+                later,
+                [60, 60, 60, 60],
+            ),
+            (
+                date(2023, 3, 15),
+                date(2023, 5, 31),
+                later,
+                [76, 75, 75, 75],
+            ),
+        ];
+
+        for (start, end, maturity, expected) in cases {
+            for (interest_method, days) in [
+                InterestMethod::Convention30_360Us,
+                InterestMethod::Convention30E360,
+                InterestMethod::Convention30E360Isda,
+                InterestMethod::Convention30_360German,
+            ]
+            .into_iter()
+            .zip(expected)
+            {
+                assert_eq!(
+                    days_30_360(start, end, maturity, interest_method),
+                    days,
+                    "{:?} {} to {}",
+                    interest_method,
+                    start,
+                    end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_counted_days() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let period = monthly_period(date(2024, 2, 1), date(2024, 3, 31));
+        let (start, end) = (period.start, period.end);
+
+        assert_eq!(
+            InterestMethod::Actual365.counted_days(start, end, &period),
+            60
+        );
+        assert_eq!(
+            InterestMethod::NoLeap365.counted_days(start, end, &period),
+            59
+        );
+        assert_eq!(
+            InterestMethod::Business252.counted_days(start, end, &period),
+            42
+        );
+        assert_eq!(
+            InterestMethod::Business252.counted_days(date(2024, 2, 3), date(2024, 2, 4), &period),
+            0
+        );
+        assert_eq!(
+            InterestMethod::NoLeap365.accrued_days(start, end, &period),
+            Decimal::from(59)
+        );
+    }
+
+    #[test]
+    fn test_fixed_period_days() {
+        assert_eq!(
+            InterestMethod::Convention30_360.fixed_period_days(PaymentFrequency::Quarterly),
+            Some(90)
+        );
+        assert_eq!(
+            InterestMethod::Convention30_360Us.fixed_period_days(PaymentFrequency::Monthly),
+            None
+        );
+        assert_eq!(
+            InterestMethod::Actual365.fixed_period_days(PaymentFrequency::Monthly),
+            None
+        );
+    }
+
+    #[test]
+    fn test_leap_days() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(leap_days(date(2023, 1, 1), date(2023, 12, 31)), 0);
+        assert_eq!(leap_days(date(2024, 2, 29), date(2024, 2, 29)), 1);
+        assert_eq!(leap_days(date(2023, 3, 1), date(2028, 3, 1)), 2);
+    }
+
+    #[test]
+    fn test_business_days_skip_holidays() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let calendar = Calendar::EnglandAndWales;
+        let period = AccrualPeriod {
+            calendar: &calendar,
+            ..monthly_period(date(2024, 3, 2), date(2024, 4, 1))
+        };
+
+        // 21 weekdays, less Good Friday and Easter Monday
+        assert_eq!(
+            InterestMethod::Business252.counted_days(period.start, period.end, &period),
+            19
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::error::AmortisationError;
+
+/// The components of an HP or PCP vehicle deal that the amount of credit is derived from.
+///
+/// The total deposit is the customer's deposit and the part-exchange net of any finance still
+/// owed on it. Negative equity on the part-exchange reduces the deposit and so is financed with
+/// the vehicle. The dealer's deposit contribution reduces the amount of credit but isn't paid by
+/// the customer, so it's kept out of the total deposit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MotorFinanceDeal {
+    pub cash_price: Decimal,
+    #[serde(default)]
+    pub customer_deposit: Decimal,
+    #[serde(default)]
+    pub part_exchange_value: Decimal,
+    /// Outstanding finance on the part-exchange that the dealer settles.
+    #[serde(default)]
+    pub part_exchange_settlement: Decimal,
+    #[serde(default)]
+    pub dealer_deposit_contribution: Decimal,
+}
+
+impl MotorFinanceDeal {
+    pub fn new(cash_price: Decimal) -> Self {
+        MotorFinanceDeal {
+            cash_price,
+            customer_deposit: Decimal::ZERO,
+            part_exchange_value: Decimal::ZERO,
+            part_exchange_settlement: Decimal::ZERO,
+            dealer_deposit_contribution: Decimal::ZERO,
+        }
+    }
+
+    /// The part-exchange value less the finance settled on it, negative for negative equity.
+    pub fn net_part_exchange(&self) -> Decimal {
+        self.part_exchange_value - self.part_exchange_settlement
+    }
+
+    /// What the customer puts down: their deposit and the net part-exchange.
+    pub fn total_deposit(&self) -> Decimal {
+        self.customer_deposit + self.net_part_exchange()
+    }
+
+    /// The cash price less the total deposit and the dealer's deposit contribution.
+    pub fn amount_of_credit(&self) -> Decimal {
+        self.cash_price - self.total_deposit() - self.dealer_deposit_contribution
+    }
+
+    pub fn validate(&self) -> Result<(), AmortisationError> {
+        let amounts = [
+            ("cash_price", self.cash_price),
+            ("customer_deposit", self.customer_deposit),
+            ("part_exchange_value", self.part_exchange_value),
+            ("part_exchange_settlement", self.part_exchange_settlement),
+            (
+                "dealer_deposit_contribution",
+                self.dealer_deposit_contribution,
+            ),
+        ];
+        if let Some((name, amount)) = amounts
+            .into_iter()
+            .find(|(_, amount)| amount.is_sign_negative())
+        {
+            return Err(AmortisationError::NegativeDealAmount { name, amount });
+        }
+        if self.amount_of_credit() <= Decimal::ZERO {
+            return Err(AmortisationError::DepositExceedsCashPrice {
+                deposit: self.total_deposit() + self.dealer_deposit_contribution,
+                cash_price: self.cash_price,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_amount_of_credit() {
+        let deal = MotorFinanceDeal {
+            customer_deposit: dec!(2000),
+            part_exchange_value: dec!(6000),
+            part_exchange_settlement: dec!(4500),
+            dealer_deposit_contribution: dec!(750),
+            ..MotorFinanceDeal::new(dec!(25000))
+        };
+
+        assert_eq!(deal.net_part_exchange(), dec!(1500));
+        assert_eq!(deal.total_deposit(), dec!(3500));
+        assert_eq!(deal.amount_of_credit(), dec!(20750));
+        assert_eq!(deal.validate(), Ok(()));
+
+        // Negative equity is financed with the car
+        let negative_equity = MotorFinanceDeal {
+            part_exchange_settlement: dec!(7000),
+            ..deal.clone()
+        };
+        assert_eq!(negative_equity.net_part_exchange(), dec!(-1000));
+        assert_eq!(negative_equity.amount_of_credit(), dec!(23250));
+    }
+
+    #[test]
+    fn test_validate_deal() {
+        let deal = MotorFinanceDeal {
+            customer_deposit: dec!(-1),
+            ..MotorFinanceDeal::new(dec!(25000))
+        };
+        assert_eq!(
+            deal.validate(),
+            Err(AmortisationError::NegativeDealAmount {
+                name: "customer_deposit",
+                amount: dec!(-1),
+            })
+        );
+
+        let deal = MotorFinanceDeal {
+            customer_deposit: dec!(20000),
+            part_exchange_value: dec!(4000),
+            dealer_deposit_contribution: dec!(1000),
+            ..MotorFinanceDeal::new(dec!(25000))
+        };
+        assert_eq!(
+            deal.validate(),
+            Err(AmortisationError::DepositExceedsCashPrice {
+                deposit: dec!(25000),
+                cash_price: dec!(25000),
+            })
+        );
+    }
+}
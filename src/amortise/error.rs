@@ -0,0 +1,210 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmortisationError {
+    /// The payment solver gave up; `last_iterate` is the best estimate it reached.
+    NonConvergence {
+        last_iterate: Decimal,
+    },
+    FirstPaymentBeforeDisbursal {
+        disbursal_date: NaiveDate,
+        first_payment_date: NaiveDate,
+    },
+    CapitalisationBeforeDisbursal {
+        disbursal_date: NaiveDate,
+        first_capitalisation_date: NaiveDate,
+    },
+    /// Solving for a loan parameter needs the payment to be given as `fixed_payment`.
+    MissingFixedPayment,
+    /// The payment never reduces the balance to zero within the maximum term.
+    PaymentDoesNotAmortise {
+        payment: Decimal,
+    },
+    /// The solved rate isn't positive, as the payments don't repay more than the principal.
+    NonPositiveRate(Decimal),
+    /// The requested combination of options isn't supported.
+    Unsupported(&'static str),
+    InvalidTerm(u32),
+    /// More interest-only periods than payments.
+    InterestOnlyExceedsTerm {
+        interest_only_periods: u32,
+        num_payments: u32,
+    },
+    InvalidPrincipal(Decimal),
+    InvalidPaymentProfile(String),
+    BalloonExceedsPrincipal {
+        balloon: Decimal,
+        principal: Decimal,
+    },
+    /// An amount in a motor finance deal is negative.
+    NegativeDealAmount {
+        name: &'static str,
+        amount: Decimal,
+    },
+    /// A motor finance deal's deposit and dealer contribution leave nothing to finance.
+    DepositExceedsCashPrice {
+        deposit: Decimal,
+        cash_price: Decimal,
+    },
+    /// The principal isn't the amount of credit derived from the motor finance deal.
+    DealCreditMismatch {
+        principal: Decimal,
+        amount_of_credit: Decimal,
+    },
+    UnknownInterestMethod(String),
+    UnknownInterestType(String),
+    UnknownPaymentFrequency(String),
+    UnknownCalendar(String),
+    UnknownBusinessDayConvention(String),
+    UnknownRateChangePayment(String),
+    UnknownAmortisationStyle(String),
+    /// A line of a holiday list that isn't a YYYY-MM-DD date.
+    InvalidHolidayDate(String),
+    /// A holiday list file couldn't be read.
+    HolidayFile {
+        path: String,
+        reason: String,
+    },
+    /// A line of a fixings file that isn't a `date,rate` pair.
+    InvalidFixing(String),
+    /// A fixings file couldn't be read.
+    FixingFile {
+        path: String,
+        reason: String,
+    },
+    /// The rate index has no fixing on or before the date a rate is observed.
+    MissingFixing(NaiveDate),
+    FloorExceedsCap {
+        floor: Decimal,
+        cap: Decimal,
+    },
+    /// A calculation exceeded the range of `Decimal`.
+    Overflow,
+}
+
+impl fmt::Display for AmortisationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmortisationError::NonConvergence { last_iterate } => write!(
+                f,
+                "failed to converge on a payment (last estimate {})",
+                last_iterate
+            ),
+            AmortisationError::FirstPaymentBeforeDisbursal {
+                disbursal_date,
+                first_payment_date,
+            } => write!(
+                f,
+                "first payment date {} is before the disbursal date {}",
+                first_payment_date, disbursal_date
+            ),
+            AmortisationError::CapitalisationBeforeDisbursal {
+                disbursal_date,
+                first_capitalisation_date,
+            } => write!(
+                f,
+                "first capitalisation date {} is before the disbursal date {}",
+                first_capitalisation_date, disbursal_date
+            ),
+            AmortisationError::MissingFixedPayment => {
+                write!(f, "a fixed payment is required to solve for this value")
+            }
+            AmortisationError::PaymentDoesNotAmortise { payment } => {
+                write!(f, "payment {} never repays the loan", payment)
+            }
+            AmortisationError::NonPositiveRate(rate) => {
+                write!(f, "solved rate {} is not positive", rate)
+            }
+            AmortisationError::Unsupported(what) => write!(f, "unsupported: {}", what),
+            AmortisationError::InvalidTerm(num_payments) => write!(
+                f,
+                "number of payments must be at least 1 (got {})",
+                num_payments
+            ),
+            AmortisationError::InterestOnlyExceedsTerm {
+                interest_only_periods,
+                num_payments,
+            } => write!(
+                f,
+                "{} interest-only periods exceed the {} payments",
+                interest_only_periods, num_payments
+            ),
+            AmortisationError::InvalidPaymentProfile(reason) => {
+                write!(f, "invalid payment profile: {}", reason)
+            }
+            AmortisationError::InvalidPrincipal(principal) => {
+                write!(f, "principal must be positive (got {})", principal)
+            }
+            AmortisationError::BalloonExceedsPrincipal { balloon, principal } => write!(
+                f,
+                "balloon payment {} exceeds the principal {}",
+                balloon, principal
+            ),
+            AmortisationError::NegativeDealAmount { name, amount } => {
+                write!(f, "deal {} {} is negative", name, amount)
+            }
+            AmortisationError::DepositExceedsCashPrice {
+                deposit,
+                cash_price,
+            } => write!(
+                f,
+                "deposit and dealer contribution of {} leave nothing of the cash price {} to finance",
+                deposit, cash_price
+            ),
+            AmortisationError::DealCreditMismatch {
+                principal,
+                amount_of_credit,
+            } => write!(
+                f,
+                "principal {} differs from the deal's amount of credit {}",
+                principal, amount_of_credit
+            ),
+            AmortisationError::UnknownInterestMethod(method) => {
+                write!(f, "unknown interest method '{}'", method)
+            }
+            AmortisationError::UnknownInterestType(interest_type) => {
+                write!(f, "unknown interest type '{}'", interest_type)
+            }
+            AmortisationError::UnknownPaymentFrequency(frequency) => {
+                write!(f, "unknown payment frequency '{}'", frequency)
+            }
+            AmortisationError::UnknownCalendar(calendar) => {
+                write!(f, "unknown holiday calendar '{}'", calendar)
+            }
+            AmortisationError::UnknownBusinessDayConvention(convention) => {
+                write!(f, "unknown business day convention '{}'", convention)
+            }
+            AmortisationError::UnknownRateChangePayment(treatment) => {
+                write!(f, "unknown rate change payment treatment '{}'", treatment)
+            }
+            AmortisationError::UnknownAmortisationStyle(style) => {
+                write!(f, "unknown amortisation style '{}'", style)
+            }
+            AmortisationError::InvalidHolidayDate(line) => {
+                write!(f, "invalid holiday date '{}', expected YYYY-MM-DD", line)
+            }
+            AmortisationError::HolidayFile { path, reason } => {
+                write!(f, "could not read holidays from {}: {}", path, reason)
+            }
+            AmortisationError::InvalidFixing(line) => write!(
+                f,
+                "invalid index fixing '{}', expected YYYY-MM-DD,RATE",
+                line
+            ),
+            AmortisationError::FixingFile { path, reason } => {
+                write!(f, "could not read index fixings from {}: {}", path, reason)
+            }
+            AmortisationError::MissingFixing(date) => {
+                write!(f, "no index fixing on or before {}", date)
+            }
+            AmortisationError::FloorExceedsCap { floor, cap } => {
+                write!(f, "rate floor {} exceeds the cap {}", floor, cap)
+            }
+            AmortisationError::Overflow => write!(f, "calculation overflowed"),
+        }
+    }
+}
+
+impl std::error::Error for AmortisationError {}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::calendar::HolidayCalendar;
+use super::error::AmortisationError;
+use super::frequency::PaymentFrequency;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Fixings of a reference rate such as SOFR, SONIA or a base rate, as annual rates by date.
+///
+/// Each fixing holds until the next one, so dates after the last historic fixing can carry a
+/// forward curve.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RateIndex(BTreeMap<NaiveDate, Decimal>);
+
+impl RateIndex {
+    pub fn new(fixings: impl IntoIterator<Item = (NaiveDate, Decimal)>) -> Self {
+        RateIndex(fixings.into_iter().collect())
+    }
+
+    /// Reads fixings from a CSV file of `date,rate` lines, with rates in percent as they are
+    /// published.
+    pub fn from_csv_file(path: impl AsRef<Path>) -> Result<Self, AmortisationError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| AmortisationError::FixingFile {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        contents.parse()
+    }
+
+    /// The latest fixing on or before `date`.
+    pub fn fixing_on(&self, date: NaiveDate) -> Option<Decimal> {
+        self.0.range(..=date).next_back().map(|(_, rate)| *rate)
+    }
+}
+
+impl FromStr for RateIndex {
+    type Err = AmortisationError;
+
+    /// Parses `YYYY-MM-DD,RATE` lines with the rate in percent. A header line and blank lines
+    /// are skipped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .peekable();
+        let is_header = |line: &&str| {
+            let first = line.split(',').next().unwrap_or_default().trim();
+            NaiveDate::parse_from_str(first, "%Y-%m-%d").is_err()
+        };
+        lines.next_if(is_header);
+
+        lines
+            .map(|line| {
+                let invalid = || AmortisationError::InvalidFixing(line.to_string());
+                let (date, rate) = line.split_once(',').ok_or_else(invalid)?;
+                let date =
+                    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|_| invalid())?;
+                let rate = Decimal::from_str(rate.trim()).map_err(|_| invalid())?;
+                Ok((date, rate / Decimal::ONE_HUNDRED))
+            })
+            .collect::<Result<_, _>>()
+            .map(RateIndex)
+    }
+}
+
+/// A rate set from an index plus a margin, reset periodically.
+///
+/// The floor and cap bound the index fixing before the margin is added, so a floor of zero
+/// stops a negative index eating into the margin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FloatingRate {
+    pub index: RateIndex,
+    pub margin: Decimal,
+    /// How often the rate resets, defaults to the payment frequency.
+    #[serde(default)]
+    pub reset_frequency: Option<PaymentFrequency>,
+    /// Business days before each reset that the index is observed.
+    #[serde(default)]
+    pub lookback_days: u32,
+    #[serde(default)]
+    pub floor: Option<Decimal>,
+    #[serde(default)]
+    pub cap: Option<Decimal>,
+}
+
+impl FloatingRate {
+    pub fn new(index: RateIndex, margin: Decimal) -> Self {
+        FloatingRate {
+            index,
+            margin,
+            reset_frequency: None,
+            lookback_days: 0,
+            floor: None,
+            cap: None,
+        }
+    }
+
+    /// The date the index is observed for a reset on `reset_date`.
+    pub fn observation_date(
+        &self,
+        reset_date: NaiveDate,
+        calendar: &dyn HolidayCalendar,
+    ) -> NaiveDate {
+        calendar.business_days_before(reset_date, self.lookback_days)
+    }
+
+    /// The annual rate set on `reset_date`: the observed fixing, floored and capped, plus the
+    /// margin. `None` if the index has no fixing by the observation date.
+    pub fn rate_on(
+        &self,
+        reset_date: NaiveDate,
+        calendar: &dyn HolidayCalendar,
+    ) -> Option<Decimal> {
+        let fixing = self
+            .index
+            .fixing_on(self.observation_date(reset_date, calendar))?;
+        let floored = self.floor.map_or(fixing, |floor| fixing.max(floor));
+        let bounded = self.cap.map_or(floored, |cap| floored.min(cap));
+        Some(bounded + self.margin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amortise::calendar::Calendar;
+    use rust_decimal_macros::dec;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_rate_index_from_csv() {
+        let index: RateIndex = "date,rate\n2024-01-02,5.19\n\n2024-01-03, 5.20\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            index,
+            RateIndex::new([
+                (ymd(2024, 1, 2), dec!(0.0519)),
+                (ymd(2024, 1, 3), dec!(0.0520))
+            ])
+        );
+        assert_eq!(index.fixing_on(ymd(2024, 1, 1)), None);
+        assert_eq!(index.fixing_on(ymd(2024, 1, 2)), Some(dec!(0.0519)));
+        assert_eq!(index.fixing_on(ymd(2024, 6, 30)), Some(dec!(0.0520)));
+
+        assert_eq!(
+            "2024-01-02,5.19\n2024-01-03".parse::<RateIndex>(),
+            Err(AmortisationError::InvalidFixing("2024-01-03".to_string()))
+        );
+        assert!(matches!(
+            RateIndex::from_csv_file("does/not/exist.csv"),
+            Err(AmortisationError::FixingFile { .. })
+        ));
+    }
+
+    #[test]
+    fn test_floating_rate_on() {
+        let index = RateIndex::new([
+            (ymd(2024, 3, 25), dec!(-0.0010)),
+            (ymd(2024, 3, 28), dec!(0.0450)),
+            (ymd(2024, 4, 2), dec!(0.0700)),
+        ]);
+        let calendar = Calendar::EnglandAndWales;
+        let rate = FloatingRate {
+            lookback_days: 2,
+            ..FloatingRate::new(index, dec!(0.02))
+        };
+
+        // Two business days before 3 April is 28 March, over the Easter weekend
+        assert_eq!(
+            rate.observation_date(ymd(2024, 4, 3), &calendar),
+            ymd(2024, 3, 28)
+        );
+        assert_eq!(rate.rate_on(ymd(2024, 4, 3), &calendar), Some(dec!(0.0650)));
+        assert_eq!(
+            rate.rate_on(ymd(2024, 3, 27), &calendar),
+            Some(dec!(0.0190))
+        );
+        assert_eq!(rate.rate_on(ymd(2024, 3, 26), &calendar), None);
+
+        let bounded = FloatingRate {
+            floor: Some(dec!(0)),
+            cap: Some(dec!(0.06)),
+            ..rate
+        };
+        assert_eq!(
+            bounded.rate_on(ymd(2024, 3, 27), &calendar),
+            Some(dec!(0.02))
+        );
+        assert_eq!(
+            bounded.rate_on(ymd(2024, 4, 3), &calendar),
+            Some(dec!(0.0650))
+        );
+        assert_eq!(
+            bounded.rate_on(ymd(2024, 4, 5), &calendar),
+            Some(dec!(0.08))
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::str::FromStr;
+
+use super::error::AmortisationError;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum PaymentFrequency {
+    Weekly,
+    Fortnightly,
+    FourWeekly,
+    #[default]
+    Monthly,
+    Quarterly,
+    SemiAnnual,
+    Annual,
+}
+
+impl FromStr for PaymentFrequency {
+    type Err = AmortisationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Weekly" => Ok(PaymentFrequency::Weekly),
+            "Fortnightly" => Ok(PaymentFrequency::Fortnightly),
+            "FourWeekly" => Ok(PaymentFrequency::FourWeekly),
+            "Monthly" => Ok(PaymentFrequency::Monthly),
+            "Quarterly" => Ok(PaymentFrequency::Quarterly),
+            "SemiAnnual" => Ok(PaymentFrequency::SemiAnnual),
+            "Annual" => Ok(PaymentFrequency::Annual),
+            _ => Err(AmortisationError::UnknownPaymentFrequency(s.to_string())),
+        }
+    }
+}
+
+impl PaymentFrequency {
+    pub fn periods_per_year(&self) -> u32 {
+        match self {
+            PaymentFrequency::Weekly => 52,
+            PaymentFrequency::Fortnightly => 26,
+            PaymentFrequency::FourWeekly => 13,
+            PaymentFrequency::Monthly => 12,
+            PaymentFrequency::Quarterly => 4,
+            PaymentFrequency::SemiAnnual => 2,
+            PaymentFrequency::Annual => 1,
+        }
+    }
+
+    /// Days charged per period under the 30/360 convention.
+    pub fn days_30_360(&self) -> u32 {
+        match self {
+            PaymentFrequency::Weekly => 7,
+            PaymentFrequency::Fortnightly => 14,
+            PaymentFrequency::FourWeekly => 28,
+            PaymentFrequency::Monthly => 30,
+            PaymentFrequency::Quarterly => 90,
+            PaymentFrequency::SemiAnnual => 180,
+            PaymentFrequency::Annual => 360,
+        }
+    }
+
+    /// The date `periods` whole periods after `date`.
+    pub fn nth_date(&self, date: NaiveDate, periods: u32) -> NaiveDate {
+        match self {
+            PaymentFrequency::Weekly => date + Days::new(7 * periods as u64),
+            PaymentFrequency::Fortnightly => date + Days::new(14 * periods as u64),
+            PaymentFrequency::FourWeekly => date + Days::new(28 * periods as u64),
+            PaymentFrequency::Monthly => date + Months::new(periods),
+            PaymentFrequency::Quarterly => date + Months::new(3 * periods),
+            PaymentFrequency::SemiAnnual => date + Months::new(6 * periods),
+            PaymentFrequency::Annual => date + Months::new(12 * periods),
+        }
+    }
+
+    fn is_month_based(&self) -> bool {
+        !matches!(
+            self,
+            PaymentFrequency::Weekly | PaymentFrequency::Fortnightly | PaymentFrequency::FourWeekly
+        )
+    }
+
+    /// The date `periods` whole periods before `date`.
+    pub fn previous_date(&self, date: NaiveDate, periods: u32) -> NaiveDate {
+        match self {
+            PaymentFrequency::Weekly => date - Days::new(7 * periods as u64),
+            PaymentFrequency::Fortnightly => date - Days::new(14 * periods as u64),
+            PaymentFrequency::FourWeekly => date - Days::new(28 * periods as u64),
+            PaymentFrequency::Monthly => date - Months::new(periods),
+            PaymentFrequency::Quarterly => date - Months::new(3 * periods),
+            PaymentFrequency::SemiAnnual => date - Months::new(6 * periods),
+            PaymentFrequency::Annual => date - Months::new(12 * periods),
+        }
+    }
+}
+
+/// Generates a schedule's dates from its first date.
+///
+/// Every date is counted from the anchor rather than stepped from the one before, so a loan
+/// first paid on the 31st is paid on the 31st again after a short month instead of drifting to
+/// the 28th. With the end-of-month rule, month-based dates (including the first) are pinned to
+/// the last day of their month; weekly dates are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateGenerator {
+    anchor: NaiveDate,
+    frequency: PaymentFrequency,
+    end_of_month: bool,
+}
+
+impl DateGenerator {
+    pub fn new(anchor: NaiveDate, frequency: PaymentFrequency, end_of_month: bool) -> Self {
+        DateGenerator {
+            anchor,
+            frequency,
+            end_of_month,
+        }
+    }
+
+    /// The date `periods` periods after the anchor; `date(0)` is the first date.
+    pub fn date(&self, periods: u32) -> NaiveDate {
+        let date = self.frequency.nth_date(self.anchor, periods);
+        if self.end_of_month && self.frequency.is_month_based() {
+            month_end(date)
+        } else {
+            date
+        }
+    }
+}
+
+fn month_end(date: NaiveDate) -> NaiveDate {
+    let first = date.with_day(1).unwrap();
+    first + Months::new(1) - Days::new(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nth_date() {
+        let date = NaiveDate::from_ymd_opt(2023, 1, 31).unwrap();
+
+        assert_eq!(
+            PaymentFrequency::Weekly.nth_date(date, 1),
+            NaiveDate::from_ymd_opt(2023, 2, 7).unwrap()
+        );
+        assert_eq!(
+            PaymentFrequency::Fortnightly.nth_date(date, 1),
+            NaiveDate::from_ymd_opt(2023, 2, 14).unwrap()
+        );
+        assert_eq!(
+            PaymentFrequency::FourWeekly.nth_date(date, 1),
+            NaiveDate::from_ymd_opt(2023, 2, 28).unwrap()
+        );
+        assert_eq!(
+            PaymentFrequency::Monthly.nth_date(date, 1),
+            NaiveDate::from_ymd_opt(2023, 2, 28).unwrap()
+        );
+        assert_eq!(
+            PaymentFrequency::Quarterly.nth_date(date, 1),
+            NaiveDate::from_ymd_opt(2023, 4, 30).unwrap()
+        );
+        assert_eq!(
+            PaymentFrequency::SemiAnnual.nth_date(date, 1),
+            NaiveDate::from_ymd_opt(2023, 7, 31).unwrap()
+        );
+        assert_eq!(
+            PaymentFrequency::Annual.nth_date(date, 1),
+            NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()
+        );
+        assert_eq!(
+            PaymentFrequency::Monthly.nth_date(date, 3),
+            NaiveDate::from_ymd_opt(2023, 4, 30).unwrap()
+        );
+        assert_eq!(PaymentFrequency::Fortnightly.nth_date(date, 0), date);
+    }
+
+    #[test]
+    fn test_payment_frequency_from_str() {
+        assert_eq!(
+            PaymentFrequency::from_str("Fortnightly"),
+            Ok(PaymentFrequency::Fortnightly)
+        );
+        assert_eq!(
+            PaymentFrequency::from_str("Daily"),
+            Err(AmortisationError::UnknownPaymentFrequency(
+                "Daily".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_date_generator_keeps_day_of_month() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let dates = DateGenerator::new(date(2023, 1, 31), PaymentFrequency::Monthly, false);
+
+        assert_eq!(
+            (0..4).map(|n| dates.date(n)).collect::<Vec<_>>(),
+            vec![
+                date(2023, 1, 31),
+                date(2023, 2, 28),
+                date(2023, 3, 31),
+                date(2023, 4, 30)
+            ]
+        );
+
+        let dates = DateGenerator::new(date(2023, 11, 30), PaymentFrequency::Quarterly, false);
+        assert_eq!(dates.date(1), date(2024, 2, 29));
+        assert_eq!(dates.date(2), date(2024, 5, 30));
+    }
+
+    #[test]
+    fn test_date_generator_end_of_month() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let dates = DateGenerator::new(date(2024, 2, 29), PaymentFrequency::Monthly, true);
+
+        assert_eq!(
+            (0..4).map(|n| dates.date(n)).collect::<Vec<_>>(),
+            vec![
+                date(2024, 2, 29),
+                date(2024, 3, 31),
+                date(2024, 4, 30),
+                date(2024, 5, 31)
+            ]
+        );
+        // Without the rule the 29th is kept
+        let dates = DateGenerator::new(date(2024, 2, 29), PaymentFrequency::Monthly, false);
+        assert_eq!(dates.date(1), date(2024, 3, 29));
+
+        // Pins a mid-month anchor too, and leaves weekly dates alone
+        let dates = DateGenerator::new(date(2024, 1, 15), PaymentFrequency::SemiAnnual, true);
+        assert_eq!(dates.date(0), date(2024, 1, 31));
+        assert_eq!(dates.date(1), date(2024, 7, 31));
+        let dates = DateGenerator::new(date(2024, 1, 15), PaymentFrequency::Weekly, true);
+        assert_eq!(dates.date(1), date(2024, 1, 22));
+    }
+}
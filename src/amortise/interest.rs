@@ -1,111 +1,160 @@
 use std::str::FromStr;
 
+use super::day_count::{AccrualPeriod, DayCounter};
+use super::error::AmortisationError;
+use super::frequency::PaymentFrequency;
+use super::rate::DailyRates;
 use super::utils::round_decimal;
-use chrono::{Days, NaiveDate};
+use chrono::Days;
 use rust_decimal::prelude::RoundingStrategy;
 use rust_decimal::{Decimal, MathematicalOps};
+use serde::{Deserialize, Serialize};
 
 const INTEREST_SCALE: u32 = 2;
 const INTEREST_PRECISION: u32 = 28;
 const INTEREST_ROUNDING: RoundingStrategy = RoundingStrategy::MidpointNearestEven;
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Default, Serialize, Deserialize)]
 pub enum InterestMethod {
+    /// Every period is charged a fixed number of days (30 for monthly), whatever the dates.
     Convention30_360,
+    /// 30/360 US (Bond Basis), with the end-of-February rules.
+    Convention30_360Us,
+    /// 30E/360 (Eurobond): the 31st counts as the 30th.
+    Convention30E360,
+    /// 30E/360 ISDA: month ends count as the 30th, except a final payment at the end of February.
+    Convention30E360Isda,
+    /// 30/360 German: month ends, including the end of February, always count as the 30th.
+    Convention30_360German,
     Actual365,
     Actual360,
-    ActualActual,
+    /// Actual/364, for money-market products.
+    Actual364,
+    /// Actual/365L (ISMA Year): a 366-day year when the period ends in a leap year, or for
+    /// annual payments when it contains 29 February.
+    #[serde(alias = "Actual365L")]
+    Actual365Leap,
+    /// NL/365: actual days less any 29 February, over a 365-day year.
+    #[serde(alias = "NL365")]
+    NoLeap365,
+    /// Business/252: weekdays over a 252-day year, as used in Brazil.
+    #[serde(alias = "Bus252")]
+    Business252,
+    /// Actual/Actual ISDA: days in leap years accrue at 1/366 of the annual rate, other days
+    /// at 1/365.
+    #[default]
+    #[serde(alias = "ActualActual")]
+    ActualActualIsda,
+    /// Actual/Actual ICMA: each full period accrues 1/frequency of the annual rate, and
+    /// partial periods in proportion to their share of the period's actual days.
+    ActualActualIcma,
 }
 
 impl FromStr for InterestMethod {
-    type Err = ();
+    type Err = AmortisationError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Convention30_360" => Ok(InterestMethod::Convention30_360),
+            "Convention30_360Us" => Ok(InterestMethod::Convention30_360Us),
+            "Convention30E360" => Ok(InterestMethod::Convention30E360),
+            "Convention30E360Isda" => Ok(InterestMethod::Convention30E360Isda),
+            "Convention30_360German" => Ok(InterestMethod::Convention30_360German),
             "Actual365" => Ok(InterestMethod::Actual365),
             "Actual360" => Ok(InterestMethod::Actual360),
-            "ActualActual" => Ok(InterestMethod::ActualActual),
-            _ => Err(()),
+            "Actual364" => Ok(InterestMethod::Actual364),
+            "Actual365Leap" | "Actual365L" => Ok(InterestMethod::Actual365Leap),
+            "NoLeap365" | "NL365" => Ok(InterestMethod::NoLeap365),
+            "Business252" | "Bus252" => Ok(InterestMethod::Business252),
+            "ActualActualIsda" | "ActualActual" => Ok(InterestMethod::ActualActualIsda),
+            "ActualActualIcma" => Ok(InterestMethod::ActualActualIcma),
+            _ => Err(AmortisationError::UnknownInterestMethod(s.to_string())),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Default, Serialize, Deserialize)]
 pub enum InterestType {
+    #[default]
     Simple,
     Compound,
 }
 impl FromStr for InterestType {
-    type Err = ();
+    type Err = AmortisationError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Simple" => Ok(InterestType::Simple),
             "Compound" => Ok(InterestType::Compound),
-            _ => Err(()),
+            _ => Err(AmortisationError::UnknownInterestType(s.to_string())),
         }
     }
 }
 
-pub fn get_daily_interest_rate(annual_rate: Decimal, interest_method: InterestMethod) -> Decimal {
-    let daily_rate: Decimal;
-
-    match interest_method {
-        InterestMethod::Convention30_360 => {
-            daily_rate = annual_rate / Decimal::from(360);
-        }
-        InterestMethod::Actual365 => {
-            daily_rate = annual_rate / Decimal::from(365);
-        }
-        InterestMethod::Actual360 => {
-            daily_rate = annual_rate / Decimal::from(360);
-        }
-        InterestMethod::ActualActual => {
-            daily_rate = annual_rate / Decimal::from(365); // adjusted later for leap years
-        }
-    }
-
-    daily_rate
+pub fn get_daily_interest_rate(annual_rate: Decimal, day_counter: &dyn DayCounter) -> Decimal {
+    annual_rate / day_counter.days_in_year()
 }
 
 pub fn calculate_period_interest(
-    start_date: NaiveDate,
-    to_date: NaiveDate,
-    payment_date: NaiveDate,
-    daily_rate: Decimal,
+    period: &AccrualPeriod,
+    daily_rates: &DailyRates,
     balance: Decimal,
     payment_amount: Decimal,
-    interest_method: InterestMethod,
+    day_counter: &dyn DayCounter,
 ) -> (Decimal, u32) {
     let mut interest: Decimal;
     let mut days: u32;
 
-    if interest_method == InterestMethod::Convention30_360 {
-        interest = Decimal::from(30) * balance * daily_rate;
-        days = 30;
+    if let Some(fixed_days) = day_counter.fixed_period_days(period.frequency) {
+        days = fixed_days;
+        if daily_rates.next_change(period.start, period.end).is_none() {
+            interest = Decimal::from(days) * balance * daily_rates.rate_on(period.start);
+        } else {
+            // The fixed days are shared between the rates in proportion to calendar days
+            let period_days = Decimal::from((period.end - period.start).num_days() + 1);
+            interest = Decimal::from(0);
+            let mut rate_start = period.start;
+            while rate_start <= period.end {
+                let rate_end = daily_rates
+                    .next_change(rate_start, period.end)
+                    .map_or(period.end, |date| date - Days::new(1));
+                let rate_days = Decimal::from((rate_end - rate_start).num_days() + 1);
+                interest += Decimal::from(days) * rate_days / period_days
+                    * balance
+                    * daily_rates.rate_on(rate_start);
+                rate_start = rate_end + Days::new(1);
+            }
+        }
     } else {
         days = 0;
         interest = Decimal::from(0);
-        let mut current_date = start_date;
+        let mut segment_start = period.start;
+        let to_date = period.end;
+        let payment_date = period.payment_date;
 
         let mut balance_m = balance;
-        let mut daily_rate_m = daily_rate;
-        while current_date <= to_date {
-            if interest_method == InterestMethod::ActualActual && current_date.leap_year() {
-                // Adjust daily rate for leap year
-                daily_rate_m *= Decimal::from(365) / Decimal::from(366);
+        // Interest is summed over runs of days with the same balance and rate, split on the
+        // payment date and on rate changes
+        while segment_start <= to_date {
+            let mut segment_end = to_date;
+            if payment_date > segment_start && payment_date <= segment_end {
+                segment_end = payment_date - Days::new(1);
+            }
+            if let Some(change) = daily_rates.next_change(segment_start, segment_end) {
+                segment_end = change - Days::new(1);
             }
 
             // Reduce balance on payment date
-            if current_date == payment_date {
+            if segment_start == payment_date {
                 balance_m -= payment_amount;
             }
 
-            interest += balance_m * daily_rate_m;
+            interest += balance_m
+                * daily_rates.rate_on(segment_start)
+                * day_counter.accrued_days(segment_start, segment_end, period);
 
-            current_date = current_date + Days::new(1);
-            days += 1;
+            days += day_counter.counted_days(segment_start, segment_end, period);
+            segment_start = segment_end + Days::new(1);
         }
     }
 
@@ -117,8 +166,8 @@ pub fn calculate_period_interest(
     ), days)
 }
 
-pub fn decompound_rate(annual_rate: Decimal) -> Decimal {
-    let compounds_per_year = Decimal::from(12);
+pub fn decompound_rate(annual_rate: Decimal, frequency: PaymentFrequency) -> Decimal {
+    let compounds_per_year = Decimal::from(frequency.periods_per_year());
     let one = Decimal::ONE;
 
     let rate = ((one + annual_rate).powd(one / compounds_per_year) - one) * compounds_per_year;
@@ -134,6 +183,9 @@ pub fn decompound_rate(annual_rate: Decimal) -> Decimal {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::amortise::calendar::Calendar;
+    use crate::amortise::rate::DailyRates;
+    use chrono::NaiveDate;
     use rust_decimal_macros::dec;
 
     #[test]
@@ -141,29 +193,87 @@ mod tests {
         let annual_rate = dec!(0.05); // 5% annual interest rate
 
         assert_eq!(
-            get_daily_interest_rate(annual_rate, InterestMethod::Convention30_360),
+            get_daily_interest_rate(annual_rate, &InterestMethod::Convention30_360),
             dec!(0.0001388888888888888888888889)
         );
         assert_eq!(
-            get_daily_interest_rate(annual_rate, InterestMethod::Actual365),
+            get_daily_interest_rate(annual_rate, &InterestMethod::Actual365),
             dec!(0.0001369863013698630136986301)
         );
         assert_eq!(
-            get_daily_interest_rate(annual_rate, InterestMethod::Actual360),
+            get_daily_interest_rate(annual_rate, &InterestMethod::Actual360),
             dec!(0.0001388888888888888888888889)
         );
         assert_eq!(
-            get_daily_interest_rate(annual_rate, InterestMethod::ActualActual),
+            get_daily_interest_rate(annual_rate, &InterestMethod::ActualActualIsda),
             dec!(0.0001369863013698630136986301)
         );
+        assert_eq!(
+            get_daily_interest_rate(annual_rate, &InterestMethod::ActualActualIcma),
+            dec!(0.0001369863013698630136986301)
+        );
+        assert_eq!(
+            get_daily_interest_rate(annual_rate, &InterestMethod::Actual364),
+            dec!(0.0001373626373626373626373626)
+        );
+        assert_eq!(
+            get_daily_interest_rate(annual_rate, &InterestMethod::Business252),
+            dec!(0.0001984126984126984126984127)
+        );
+    }
+
+    #[test]
+    fn test_interest_method_from_str() {
+        assert_eq!(
+            InterestMethod::from_str("Actual360"),
+            Ok(InterestMethod::Actual360)
+        );
+        assert_eq!(
+            InterestMethod::from_str("Convention30E360Isda"),
+            Ok(InterestMethod::Convention30E360Isda)
+        );
+        assert_eq!(
+            InterestMethod::from_str("ActualActual"),
+            Ok(InterestMethod::ActualActualIsda)
+        );
+        assert_eq!(
+            InterestMethod::from_str("ActualActualIcma"),
+            Ok(InterestMethod::ActualActualIcma)
+        );
+        assert_eq!(
+            InterestMethod::from_str("Actual365L"),
+            Ok(InterestMethod::Actual365Leap)
+        );
+        assert_eq!(
+            InterestMethod::from_str("NL365"),
+            Ok(InterestMethod::NoLeap365)
+        );
+        assert_eq!(
+            InterestMethod::from_str("Business252"),
+            Ok(InterestMethod::Business252)
+        );
+        assert_eq!(
+            InterestMethod::from_str("Actual366"),
+            Err(AmortisationError::UnknownInterestMethod(
+                "Actual366".to_string()
+            ))
+        );
+        assert_eq!(
+            InterestType::from_str("Fancy"),
+            Err(AmortisationError::UnknownInterestType("Fancy".to_string()))
+        );
     }
 
     #[test]
     fn test_decompound_rate() {
         let rate = dec!(0.0512); // 5.12% EAR
-        let decompounded_rate = decompound_rate(rate);
+        let decompounded_rate = decompound_rate(rate, PaymentFrequency::Monthly);
 
         assert_eq!(decompounded_rate, dec!(0.050036));
+
+        let quarterly_rate = decompound_rate(rate, PaymentFrequency::Quarterly);
+
+        assert_eq!(quarterly_rate, dec!(0.050245));
     }
 
     #[test]
@@ -177,17 +287,40 @@ mod tests {
         let interest_method = InterestMethod::Convention30_360;
 
         let (interest, days) = calculate_period_interest(
-            start_date,
-            to_date,
-            payment_date,
-            daily_rate,
+            &AccrualPeriod {
+                start: start_date,
+                end: to_date,
+                payment_date,
+                maturity: to_date,
+                frequency: PaymentFrequency::Monthly,
+                calendar: &Calendar::WeekendsOnly,
+            },
+            &DailyRates::flat(daily_rate),
             balance,
             payment_amount,
-            interest_method,
+            &interest_method,
         );
 
         assert_eq!(interest, dec!(4.17));
         assert_eq!(days, 30);
+
+        let (interest, days) = calculate_period_interest(
+            &AccrualPeriod {
+                start: start_date,
+                end: to_date,
+                payment_date,
+                maturity: to_date,
+                frequency: PaymentFrequency::Quarterly,
+                calendar: &Calendar::WeekendsOnly,
+            },
+            &DailyRates::flat(daily_rate),
+            balance,
+            payment_amount,
+            &interest_method,
+        );
+
+        assert_eq!(interest, dec!(12.50));
+        assert_eq!(days, 90);
     }
 
     #[test]
@@ -201,17 +334,23 @@ mod tests {
         let interest_method = InterestMethod::Actual365;
 
         let (interest, days) = calculate_period_interest(
-            start_date,
-            to_date,
-            payment_date,
-            daily_rate,
+            &AccrualPeriod {
+                start: start_date,
+                end: to_date,
+                payment_date,
+                maturity: to_date,
+                frequency: PaymentFrequency::Monthly,
+                calendar: &Calendar::WeekendsOnly,
+            },
+            &DailyRates::flat(daily_rate),
             balance,
             payment_amount,
-            interest_method,
+            &interest_method,
         );
 
         assert_eq!(interest, dec!(3.89));
         assert_eq!(days, 30);
+
     }
 
     #[test]
@@ -225,13 +364,18 @@ mod tests {
         let interest_method = InterestMethod::Actual360;
 
         let (interest, days) = calculate_period_interest(
-            start_date,
-            to_date,
-            payment_date,
-            daily_rate,
+            &AccrualPeriod {
+                start: start_date,
+                end: to_date,
+                payment_date,
+                maturity: to_date,
+                frequency: PaymentFrequency::Monthly,
+                calendar: &Calendar::WeekendsOnly,
+            },
+            &DailyRates::flat(daily_rate),
             balance,
             payment_amount,
-            interest_method,
+            &interest_method,
         );
 
         assert_eq!(interest, dec!(3.94));
@@ -240,27 +384,385 @@ mod tests {
     }
 
     #[test]
-    fn test_calculate_period_interest_actualactual() {
+    fn test_calculate_period_interest_actualactual_isda() {
         let start_date = NaiveDate::from_ymd_opt(2023, 1, 1).expect("Invalid date");
         let to_date = NaiveDate::from_ymd_opt(2023, 1, 30).expect("Invalid date");
         let payment_date = NaiveDate::from_ymd_opt(2023, 1, 15).expect("Invalid date");
         let daily_rate = dec!(0.0001369863013698630136986301);
         let balance = dec!(1000);
         let payment_amount = dec!(100);
-        let interest_method = InterestMethod::ActualActual;
+        let interest_method = InterestMethod::ActualActualIsda;
 
         let (interest, days) = calculate_period_interest(
-            start_date,
-            to_date,
-            payment_date,
-            daily_rate,
+            &AccrualPeriod {
+                start: start_date,
+                end: to_date,
+                payment_date,
+                maturity: to_date,
+                frequency: PaymentFrequency::Monthly,
+                calendar: &Calendar::WeekendsOnly,
+            },
+            &DailyRates::flat(daily_rate),
             balance,
             payment_amount,
-            interest_method,
+            &interest_method,
         );
 
         assert_eq!(interest, dec!(3.89));
         assert_eq!(days, 30);
+    }
+
+    /// Day-by-day accrual, as a reference for the segment sums in `calculate_period_interest`.
+    fn day_by_day_interest(
+        start_date: NaiveDate,
+        to_date: NaiveDate,
+        payment_date: NaiveDate,
+        daily_rate: Decimal,
+        balance: Decimal,
+        payment_amount: Decimal,
+        interest_method: InterestMethod,
+    ) -> (Decimal, u32) {
+        let mut days = 0;
+        let mut interest = Decimal::from(0);
+        let mut current_date = start_date;
+
+        let mut balance_m = balance;
+        while current_date <= to_date {
+            let mut daily_rate_m = daily_rate;
+            if interest_method == InterestMethod::ActualActualIsda && current_date.leap_year() {
+                daily_rate_m = daily_rate * Decimal::from(365) / Decimal::from(366);
+            }
+            if current_date == payment_date {
+                balance_m -= payment_amount;
+            }
+            interest += balance_m * daily_rate_m;
+            current_date = current_date + Days::new(1);
+            days += 1;
+        }
+
+        (
+            round_decimal(
+                interest,
+                Some(INTEREST_PRECISION),
+                Some(INTEREST_SCALE),
+                Some(INTEREST_ROUNDING),
+            ),
+            days,
+        )
+    }
+
+    #[test]
+    fn test_calculate_period_interest_matches_day_by_day() {
+        let mut start_date = NaiveDate::from_ymd_opt(2023, 1, 25).unwrap();
+        let last_start = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+        while start_date <= last_start {
+            for period_days in [1, 7, 31, 92, 366] {
+                let to_date = start_date + Days::new(period_days - 1);
+                for payment_date in [
+                    start_date - Days::new(1),
+                    start_date,
+                    start_date + Days::new(period_days / 2),
+                    to_date,
+                    to_date + Days::new(1),
+                ] {
+                    for interest_method in [
+                        InterestMethod::Actual365,
+                        InterestMethod::Actual360,
+                        InterestMethod::ActualActualIsda,
+                    ] {
+                        for (annual_rate, balance) in
+                            [(dec!(0.05), dec!(1000)), (dec!(0.199), dec!(123456.78))]
+                        {
+                            let daily_rate = get_daily_interest_rate(annual_rate, &interest_method);
+                            let payment_amount = dec!(400);
+
+                            assert_eq!(
+                                calculate_period_interest(
+                                    &AccrualPeriod {
+                                        start: start_date,
+                                        end: to_date,
+                                        payment_date,
+                                        maturity: to_date,
+                                        frequency: PaymentFrequency::Monthly,
+                                        calendar: &Calendar::WeekendsOnly,
+                                    },
+                                    &DailyRates::flat(daily_rate),
+                                    balance,
+                                    payment_amount,
+                                    &interest_method,
+                                ),
+                                day_by_day_interest(
+                                    start_date,
+                                    to_date,
+                                    payment_date,
+                                    daily_rate,
+                                    balance,
+                                    payment_amount,
+                                    interest_method,
+                                ),
+                                "{:?} {} to {}, paid {}",
+                                interest_method,
+                                start_date,
+                                to_date,
+                                payment_date
+                            );
+                        }
+                    }
+                }
+            }
+            start_date = start_date + Days::new(37);
+        }
+    }
 
+    fn period_interest(
+        start_date: NaiveDate,
+        to_date: NaiveDate,
+        interest_method: InterestMethod,
+    ) -> Decimal {
+        // Payment falls after the period so the balance is constant
+        calculate_period_interest(
+            &AccrualPeriod {
+                start: start_date,
+                end: to_date,
+                payment_date: to_date + Days::new(1),
+                maturity: to_date,
+                frequency: PaymentFrequency::Monthly,
+                calendar: &Calendar::WeekendsOnly,
+            },
+            &DailyRates::flat(get_daily_interest_rate(dec!(0.05), &interest_method)),
+            dec!(10000),
+            dec!(500),
+            &interest_method,
+        )
+        .0
+    }
+
+    #[test]
+    fn test_actualactual_isda_across_leap_day() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // 29 days in 2024, including 29 February: 10000 * 0.05 * 29/366
+        assert_eq!(
+            period_interest(
+                date(2024, 2, 15),
+                date(2024, 3, 14),
+                InterestMethod::ActualActualIsda
+            ),
+            dec!(39.62)
+        );
+        // The same span a year earlier has 28 days at 1/365
+        assert_eq!(
+            period_interest(
+                date(2023, 2, 15),
+                date(2023, 3, 14),
+                InterestMethod::ActualActualIsda
+            ),
+            dec!(38.36)
+        );
+        // 15 days in 2023 and 16 in 2024: 10000 * 0.05 * (15/365 + 16/366)
+        assert_eq!(
+            period_interest(
+                date(2023, 12, 17),
+                date(2024, 1, 16),
+                InterestMethod::ActualActualIsda
+            ),
+            dec!(42.41)
+        );
+        // A whole leap year accrues exactly the annual rate
+        assert_eq!(
+            period_interest(
+                date(2024, 1, 1),
+                date(2024, 12, 31),
+                InterestMethod::ActualActualIsda
+            ),
+            dec!(500.00)
+        );
+    }
+
+    #[test]
+    fn test_actualactual_icma_across_leap_day() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // A full monthly period accrues 1/12 of the annual rate, leap day or not
+        assert_eq!(
+            period_interest(
+                date(2024, 1, 30),
+                date(2024, 2, 29),
+                InterestMethod::ActualActualIcma
+            ),
+            dec!(41.67)
+        );
+        assert_eq!(
+            period_interest(
+                date(2023, 1, 29),
+                date(2023, 2, 28),
+                InterestMethod::ActualActualIcma
+            ),
+            dec!(41.67)
+        );
+        // Short first period: 29 of the 31 days from 29 January to 29 February
+        assert_eq!(
+            period_interest(
+                date(2024, 2, 1),
+                date(2024, 2, 29),
+                InterestMethod::ActualActualIcma
+            ),
+            dec!(38.98)
+        );
+        // Long first period: one full period plus 29 of the 31 days from 29 December to 29 January
+        assert_eq!(
+            period_interest(
+                date(2024, 1, 1),
+                date(2024, 2, 29),
+                InterestMethod::ActualActualIcma
+            ),
+            dec!(80.65)
+        );
+    }
+
+    #[test]
+    fn test_additional_day_counts() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // 28 days over a 364-day year
+        assert_eq!(
+            period_interest(
+                date(2023, 1, 1),
+                date(2023, 1, 28),
+                InterestMethod::Actual364
+            ),
+            dec!(38.46)
+        );
+        // Monthly Actual/365L uses a 366-day year when the period ends in a leap year
+        assert_eq!(
+            period_interest(
+                date(2024, 1, 1),
+                date(2024, 1, 31),
+                InterestMethod::Actual365Leap
+            ),
+            dec!(42.35)
+        );
+        assert_eq!(
+            period_interest(
+                date(2023, 1, 1),
+                date(2023, 1, 31),
+                InterestMethod::Actual365Leap
+            ),
+            dec!(42.47)
+        );
+        // NL/365 skips 29 February: 28 days at 1/365
+        assert_eq!(
+            period_interest(
+                date(2024, 2, 15),
+                date(2024, 3, 14),
+                InterestMethod::NoLeap365
+            ),
+            dec!(38.36)
+        );
+        // 23 weekdays in January 2024 over a 252-day year
+        assert_eq!(
+            period_interest(
+                date(2024, 1, 1),
+                date(2024, 1, 31),
+                InterestMethod::Business252
+            ),
+            dec!(45.63)
+        );
+    }
+
+    #[test]
+    fn test_actual365_leap_annual() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let annual_interest = |start, end, interest_method: InterestMethod| {
+            calculate_period_interest(
+                &AccrualPeriod {
+                    start,
+                    end,
+                    payment_date: end + Days::new(1),
+                    maturity: end,
+                    frequency: PaymentFrequency::Annual,
+                    calendar: &Calendar::WeekendsOnly,
+                },
+                &DailyRates::flat(get_daily_interest_rate(dec!(0.05), &interest_method)),
+                dec!(10000),
+                dec!(500),
+                &interest_method,
+            )
+        };
+
+        // A year containing 29 February accrues exactly the annual rate under Actual/365L
+        assert_eq!(
+            annual_interest(
+                date(2023, 3, 1),
+                date(2024, 2, 29),
+                InterestMethod::Actual365Leap
+            ),
+            (dec!(500.00), 366)
+        );
+        assert_eq!(
+            annual_interest(
+                date(2023, 3, 1),
+                date(2024, 2, 29),
+                InterestMethod::Actual365
+            ),
+            (dec!(501.37), 366)
+        );
+        // Ending in a leap year without containing 29 February uses 365 days for annual payments
+        assert_eq!(
+            annual_interest(
+                date(2023, 12, 1),
+                date(2024, 1, 31),
+                InterestMethod::Actual365Leap
+            ),
+            (dec!(84.93), 62)
+        );
+    }
+
+    #[test]
+    fn test_calculate_period_interest_splits_at_rate_change() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let period = AccrualPeriod {
+            start: date(2024, 1, 1),
+            end: date(2024, 1, 31),
+            payment_date: date(2024, 2, 1),
+            maturity: date(2024, 1, 31),
+            frequency: PaymentFrequency::Monthly,
+            calendar: &Calendar::WeekendsOnly,
+        };
+        let rates = |interest_method: InterestMethod| {
+            DailyRates::new(
+                get_daily_interest_rate(dec!(0.05), &interest_method),
+                [(
+                    date(2024, 1, 16),
+                    get_daily_interest_rate(dec!(0.10), &interest_method),
+                )],
+            )
+        };
+
+        // 15 days at 5% and 16 at 10%: 10000 * (0.05 * 15 + 0.10 * 16) / 365
+        let interest_method = InterestMethod::Actual365;
+        assert_eq!(
+            calculate_period_interest(
+                &period,
+                &rates(interest_method),
+                dec!(10000),
+                dec!(500),
+                &interest_method
+            ),
+            (dec!(64.38), 31)
+        );
+        // 30/360 shares the 30 days between the rates by calendar days
+        let interest_method = InterestMethod::Convention30_360;
+        assert_eq!(
+            calculate_period_interest(
+                &period,
+                &rates(interest_method),
+                dec!(10000),
+                dec!(500),
+                &interest_method
+            ),
+            (dec!(63.17), 30)
+        );
     }
 }
@@ -0,0 +1,33 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// A single evaluation made by the solver while searching for a payment.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SolverIteration {
+    /// 1-based count of schedule evaluations so far.
+    pub iteration: u32,
+    /// The candidate value being tried, e.g. the period payment.
+    pub estimate: Decimal,
+    /// How far the resulting schedule misses its target (final balance minus any balloon).
+    pub residual: Decimal,
+}
+
+/// Receives each solver iteration, for debugging convergence without writing to stdout.
+///
+/// Implemented for any `FnMut(&SolverIteration)`, so a closure can be passed directly.
+pub trait SolverObserver {
+    fn on_iteration(&mut self, iteration: &SolverIteration);
+}
+
+impl<F: FnMut(&SolverIteration)> SolverObserver for F {
+    fn on_iteration(&mut self, iteration: &SolverIteration) {
+        self(iteration)
+    }
+}
+
+/// Observer that discards every iteration; used when the caller doesn't ask for one.
+pub(crate) struct NoopObserver;
+
+impl SolverObserver for NoopObserver {
+    fn on_iteration(&mut self, _iteration: &SolverIteration) {}
+}
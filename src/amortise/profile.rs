@@ -0,0 +1,123 @@
+use rust_decimal::{Decimal, MathematicalOps};
+use serde::{Deserialize, Serialize};
+
+use super::error::AmortisationError;
+
+/// How payments vary over the term, as weights applied to a base payment that the payment
+/// solver finds.
+///
+/// Weights count from the first payment, interest-only ones included.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PaymentProfile {
+    /// The weight of each payment in turn; the last weight carries on for any later payments.
+    Weights(Vec<Decimal>),
+    /// Payments rise by `rate` (0.03 for 3%) every `every` payments, compounding, so a yearly
+    /// step-up on a monthly loan has `every` of 12.
+    Stepped { every: u32, rate: Decimal },
+}
+
+impl PaymentProfile {
+    /// The weight of payment number `payment`, counting from 1.
+    pub fn weight(&self, payment: u32) -> Decimal {
+        match self {
+            PaymentProfile::Weights(weights) => {
+                let index = (payment.max(1) as usize - 1).min(weights.len().saturating_sub(1));
+                weights.get(index).copied().unwrap_or(Decimal::ONE)
+            }
+            PaymentProfile::Stepped { every, rate } => {
+                let steps = (payment.max(1) - 1) / (*every).max(1);
+                (Decimal::ONE + rate).powi(steps.into())
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), AmortisationError> {
+        match self {
+            PaymentProfile::Weights(weights) => {
+                if weights.is_empty() {
+                    return Err(AmortisationError::InvalidPaymentProfile(
+                        "no payment weights".to_string(),
+                    ));
+                }
+                if let Some(weight) = weights.iter().find(|weight| **weight <= Decimal::ZERO) {
+                    return Err(AmortisationError::InvalidPaymentProfile(format!(
+                        "payment weight {} is not positive",
+                        weight
+                    )));
+                }
+            }
+            PaymentProfile::Stepped { every, rate } => {
+                if *every == 0 {
+                    return Err(AmortisationError::InvalidPaymentProfile(
+                        "steps every 0 payments".to_string(),
+                    ));
+                }
+                if *rate <= -Decimal::ONE {
+                    return Err(AmortisationError::InvalidPaymentProfile(format!(
+                        "step rate {} leaves no payment",
+                        rate
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_payment_weights() {
+        let profile = PaymentProfile::Weights(vec![dec!(0.8), dec!(0.9), dec!(1)]);
+        assert_eq!(profile.weight(1), dec!(0.8));
+        assert_eq!(profile.weight(2), dec!(0.9));
+        assert_eq!(profile.weight(3), dec!(1));
+        assert_eq!(profile.weight(60), dec!(1));
+
+        let stepped = PaymentProfile::Stepped {
+            every: 12,
+            rate: dec!(0.03),
+        };
+        assert_eq!(stepped.weight(1), dec!(1));
+        assert_eq!(stepped.weight(12), dec!(1));
+        assert_eq!(stepped.weight(13), dec!(1.03));
+        assert_eq!(stepped.weight(25), dec!(1.0609));
+    }
+
+    #[test]
+    fn test_validate_payment_profile() {
+        assert_eq!(
+            PaymentProfile::Weights(Vec::new()).validate(),
+            Err(AmortisationError::InvalidPaymentProfile(
+                "no payment weights".to_string()
+            ))
+        );
+        assert_eq!(
+            PaymentProfile::Weights(vec![dec!(1), dec!(0)]).validate(),
+            Err(AmortisationError::InvalidPaymentProfile(
+                "payment weight 0 is not positive".to_string()
+            ))
+        );
+        assert_eq!(
+            PaymentProfile::Stepped {
+                every: 0,
+                rate: dec!(0.03)
+            }
+            .validate(),
+            Err(AmortisationError::InvalidPaymentProfile(
+                "steps every 0 payments".to_string()
+            ))
+        );
+        assert_eq!(
+            PaymentProfile::Stepped {
+                every: 12,
+                rate: dec!(-0.05)
+            }
+            .validate(),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::error::AmortisationError;
+
+/// A new annual rate taking effect on `effective_date`, for the day and every day after.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateChange {
+    pub effective_date: NaiveDate,
+    pub annual_rate: Decimal,
+}
+
+/// The annual rate in effect on `date`, starting at `initial` and following `changes` in date
+/// order.
+pub fn annual_rate_on(initial: Decimal, changes: &[RateChange], date: NaiveDate) -> Decimal {
+    changes
+        .iter()
+        .take_while(|change| change.effective_date <= date)
+        .last()
+        .map_or(initial, |change| change.annual_rate)
+}
+
+/// What happens to the contractual payment when the rate changes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum RateChangePayment {
+    /// The payment is recalculated to repay the balance over the remaining term at the new rate.
+    #[default]
+    Recast,
+    /// The payment is unchanged and the term lengthens or shortens until the loan is repaid.
+    FlexTerm,
+}
+
+impl FromStr for RateChangePayment {
+    type Err = AmortisationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Recast" => Ok(RateChangePayment::Recast),
+            "FlexTerm" => Ok(RateChangePayment::FlexTerm),
+            _ => Err(AmortisationError::UnknownRateChangePayment(s.to_string())),
+        }
+    }
+}
+
+/// Daily interest rates over the life of a loan, changing on given dates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyRates {
+    initial: Decimal,
+    /// Effective dates and the rates from them, in date order.
+    changes: Vec<(NaiveDate, Decimal)>,
+}
+
+impl DailyRates {
+    /// The same daily rate for every day.
+    pub fn flat(daily_rate: Decimal) -> Self {
+        DailyRates {
+            initial: daily_rate,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn new(initial: Decimal, changes: impl IntoIterator<Item = (NaiveDate, Decimal)>) -> Self {
+        let mut changes: Vec<_> = changes.into_iter().collect();
+        changes.sort_by_key(|(date, _)| *date);
+        DailyRates { initial, changes }
+    }
+
+    /// The daily rate in effect on `date`.
+    pub fn rate_on(&self, date: NaiveDate) -> Decimal {
+        self.changes
+            .iter()
+            .take_while(|(effective_date, _)| *effective_date <= date)
+            .last()
+            .map_or(self.initial, |(_, rate)| *rate)
+    }
+
+    /// The first date after `date`, up to and including `end`, that the rate changes.
+    pub fn next_change(&self, date: NaiveDate, end: NaiveDate) -> Option<NaiveDate> {
+        self.changes
+            .iter()
+            .map(|(effective_date, _)| *effective_date)
+            .find(|effective_date| *effective_date > date && *effective_date <= end)
+    }
+
+    /// Whether the rate changes on any day from `start` to `end`, both inclusive.
+    pub fn changes_between(&self, start: NaiveDate, end: NaiveDate) -> bool {
+        self.changes
+            .iter()
+            .any(|(effective_date, _)| *effective_date >= start && *effective_date <= end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_daily_rates() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let rates = DailyRates::new(
+            dec!(0.0001),
+            [
+                (date(2025, 1, 1), dec!(0.0003)),
+                (date(2024, 1, 1), dec!(0.0002)),
+            ],
+        );
+
+        assert_eq!(rates.rate_on(date(2023, 12, 31)), dec!(0.0001));
+        assert_eq!(rates.rate_on(date(2024, 1, 1)), dec!(0.0002));
+        assert_eq!(rates.rate_on(date(2024, 12, 31)), dec!(0.0002));
+        assert_eq!(rates.rate_on(date(2025, 6, 1)), dec!(0.0003));
+
+        assert_eq!(
+            rates.next_change(date(2023, 12, 15), date(2024, 1, 14)),
+            Some(date(2024, 1, 1))
+        );
+        assert_eq!(rates.next_change(date(2024, 1, 1), date(2024, 1, 31)), None);
+        assert!(rates.changes_between(date(2024, 1, 1), date(2024, 1, 31)));
+        assert!(!rates.changes_between(date(2024, 1, 2), date(2024, 1, 31)));
+
+        assert_eq!(
+            DailyRates::flat(dec!(0.0001)).rate_on(date(2024, 1, 1)),
+            dec!(0.0001)
+        );
+    }
+
+    #[test]
+    fn test_rate_change_payment_from_str() {
+        assert_eq!("FlexTerm".parse(), Ok(RateChangePayment::FlexTerm));
+        assert_eq!(
+            "Fixed".parse::<RateChangePayment>(),
+            Err(AmortisationError::UnknownRateChangePayment(
+                "Fixed".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::calendar::{BusinessDayConvention, Calendar, HolidayCalendar};
+use super::floating::RateIndex;
+use super::utils::round_decimal;
+
+/// Compounded rates are rounded to one hundred-thousandth of a percentage point.
+const COMPOUNDED_RATE_SCALE: u32 = 7;
+
+/// Interest at an overnight rate such as SONIA or SOFR compounded daily in arrears, plus a
+/// margin, following the ISDA and LMA conventions.
+///
+/// Each business day's fixing applies until the next business day. With a lookback each day
+/// of the interest period takes the fixing from `lookback_days` business days earlier, weighted
+/// by the interest period's days; with an observation shift the whole period is shifted back
+/// and the fixings are weighted by the observation period's days instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompoundedRfr {
+    /// Daily fixings of the overnight rate.
+    pub index: RateIndex,
+    pub margin: Decimal,
+    #[serde(default)]
+    pub lookback_days: u32,
+    #[serde(default)]
+    pub observation_shift: bool,
+    /// Business days of the overnight rate, such as `EnglandAndWales` for SONIA or `UsFederal`
+    /// for SOFR.
+    #[serde(default)]
+    pub calendar: Calendar,
+}
+
+impl CompoundedRfr {
+    pub fn new(index: RateIndex, margin: Decimal) -> Self {
+        CompoundedRfr {
+            index,
+            margin,
+            lookback_days: 0,
+            observation_shift: false,
+            calendar: Calendar::default(),
+        }
+    }
+
+    /// The date of the fixing used for the first day of an interest period from `start`.
+    pub fn first_fixing_date(&self, start: NaiveDate) -> NaiveDate {
+        let calendar = &self.calendar;
+        if self.observation_shift {
+            let observed = calendar.business_days_before(start, self.lookback_days);
+            BusinessDayConvention::Preceding.adjust(observed, calendar)
+        } else {
+            let rate_day = BusinessDayConvention::Preceding.adjust(start, calendar);
+            calendar.business_days_before(rate_day, self.lookback_days)
+        }
+    }
+
+    /// The rate an interest period from `start` would accrue at, margin included, if the
+    /// overnight rate stayed at its first fixing.
+    pub fn initial_rate(&self, start: NaiveDate) -> Option<Decimal> {
+        let fixing = self.index.fixing_on(self.first_fixing_date(start))?;
+        Some(fixing + self.margin)
+    }
+
+    /// The annualised compounded rate, before the margin, for the interest period `start` to
+    /// `end`, both inclusive, over a `days_in_year` basis (365 for SONIA, 360 for SOFR).
+    ///
+    /// `None` if the index has no fixing for the first day. Days before the first business day
+    /// of the period take the fixing of the business day before them.
+    pub fn compounded_rate(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        days_in_year: Decimal,
+    ) -> Option<Decimal> {
+        let calendar = &self.calendar;
+        let period_end = end + Days::new(1);
+        let (observation_start, observation_end) = if self.observation_shift {
+            (
+                calendar.business_days_before(start, self.lookback_days),
+                calendar.business_days_before(period_end, self.lookback_days),
+            )
+        } else {
+            (start, period_end)
+        };
+
+        let mut factor = Decimal::ONE;
+        let mut day = observation_start;
+        while day < observation_end {
+            let mut next = day + Days::new(1);
+            while next < observation_end && !calendar.is_business_day(next) {
+                next = next + Days::new(1);
+            }
+            let rate_day = BusinessDayConvention::Preceding.adjust(day, calendar);
+            let fixing_date = if self.observation_shift {
+                rate_day
+            } else {
+                calendar.business_days_before(rate_day, self.lookback_days)
+            };
+            let weight = Decimal::from((next - day).num_days());
+            factor *= Decimal::ONE + self.index.fixing_on(fixing_date)? * weight / days_in_year;
+            day = next;
+        }
+
+        let observation_days = Decimal::from((observation_end - observation_start).num_days());
+        Some(round_decimal(
+            (factor - Decimal::ONE) * days_in_year / observation_days,
+            None,
+            Some(COMPOUNDED_RATE_SCALE),
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Months};
+    use rust_decimal_macros::dec;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// Daily fixings from Wednesday 28 February to Friday 8 March 2024.
+    fn week_of_fixings() -> RateIndex {
+        RateIndex::new([
+            (ymd(2024, 2, 28), dec!(0.0510)),
+            (ymd(2024, 2, 29), dec!(0.0520)),
+            (ymd(2024, 3, 1), dec!(0.0530)),
+            (ymd(2024, 3, 4), dec!(0.0540)),
+            (ymd(2024, 3, 5), dec!(0.0550)),
+            (ymd(2024, 3, 6), dec!(0.0560)),
+            (ymd(2024, 3, 7), dec!(0.0570)),
+            (ymd(2024, 3, 8), dec!(0.0580)),
+        ])
+    }
+
+    #[test]
+    fn test_compounded_rate() {
+        // Interest period Monday 4 March to Sunday 10 March: Friday's fixing applies for three
+        // days. (1 + 0.054/365)(1 + 0.055/365)(1 + 0.056/365)(1 + 0.057/365)(1 + 3 * 0.058/365)
+        // - 1 = 0.0010853602..., annualised over 7 days
+        let rfr = CompoundedRfr::new(week_of_fixings(), dec!(0.02));
+        assert_eq!(
+            rfr.compounded_rate(ymd(2024, 3, 4), ymd(2024, 3, 10), dec!(365)),
+            Some(dec!(0.0565938))
+        );
+        assert_eq!(rfr.initial_rate(ymd(2024, 3, 4)), Some(dec!(0.0740)));
+
+        // A two day lookback uses the fixings of 29 February to 6 March, weighted by the
+        // interest period's days
+        let lookback = CompoundedRfr {
+            lookback_days: 2,
+            ..rfr.clone()
+        };
+        assert_eq!(
+            lookback.first_fixing_date(ymd(2024, 3, 4)),
+            ymd(2024, 2, 29)
+        );
+        assert_eq!(
+            lookback.compounded_rate(ymd(2024, 3, 4), ymd(2024, 3, 10), dec!(365)),
+            Some(dec!(0.0545922))
+        );
+
+        // With an observation shift the period is 29 February to 6 March, so Friday 1 March's
+        // fixing takes the three days of its weekend instead
+        let shift = CompoundedRfr {
+            observation_shift: true,
+            ..lookback
+        };
+        assert_eq!(shift.first_fixing_date(ymd(2024, 3, 4)), ymd(2024, 2, 29));
+        assert_eq!(
+            shift.compounded_rate(ymd(2024, 3, 4), ymd(2024, 3, 10), dec!(365)),
+            Some(dec!(0.0537347))
+        );
+
+        // SOFR compounds over a 360-day year
+        assert_eq!(
+            rfr.compounded_rate(ymd(2024, 3, 4), ymd(2024, 3, 10), dec!(360)),
+            Some(dec!(0.0565941))
+        );
+        assert_eq!(
+            rfr.compounded_rate(ymd(2024, 2, 1), ymd(2024, 2, 29), dec!(365)),
+            None
+        );
+    }
+
+    /// The ISDA and LMA compounding formula applied calendar day by calendar day, as a reference
+    /// for the business day runs in `compounded_rate`. Each day takes the fixing of the business
+    /// day it falls in, lagged for a lookback, and the rate compounds when that fixing changes.
+    fn reference_rate(rfr: &CompoundedRfr, start: NaiveDate, end: NaiveDate) -> Decimal {
+        let calendar = &rfr.calendar;
+        let shift = |date| {
+            if rfr.observation_shift {
+                calendar.business_days_before(date, rfr.lookback_days)
+            } else {
+                date
+            }
+        };
+        let days = shift(start)
+            .iter_days()
+            .take_while(|day| *day < shift(end + Days::new(1)));
+
+        let mut factor = Decimal::ONE;
+        let mut run: Option<(NaiveDate, Decimal)> = None;
+        let mut count = 0;
+        for day in days {
+            let rate_day = BusinessDayConvention::Preceding.adjust(day, calendar);
+            let fixing_date = if rfr.observation_shift {
+                rate_day
+            } else {
+                calendar.business_days_before(rate_day, rfr.lookback_days)
+            };
+            match run {
+                Some((date, run_days)) if date == fixing_date => {
+                    run = Some((date, run_days + Decimal::ONE))
+                }
+                _ => {
+                    if let Some((date, run_days)) = run {
+                        factor *= Decimal::ONE
+                            + rfr.index.fixing_on(date).unwrap() * run_days / dec!(365);
+                    }
+                    run = Some((fixing_date, Decimal::ONE));
+                }
+            }
+            count += 1;
+        }
+        if let Some((date, run_days)) = run {
+            factor *= Decimal::ONE + rfr.index.fixing_on(date).unwrap() * run_days / dec!(365);
+        }
+        round_decimal(
+            (factor - Decimal::ONE) * dec!(365) / Decimal::from(count),
+            None,
+            Some(COMPOUNDED_RATE_SCALE),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_compounded_rate_matches_reference() {
+        // A different fixing every business day of 2024, around 5%
+        let calendar = Calendar::EnglandAndWales;
+        let fixings = ymd(2023, 11, 1)
+            .iter_days()
+            .take_while(|day| *day < ymd(2025, 2, 1))
+            .filter(|day| calendar.is_business_day(*day))
+            .map(|day| {
+                (
+                    day,
+                    dec!(0.05) + Decimal::new(day.ordinal() as i64 * 37 % 50, 5),
+                )
+            })
+            .collect::<Vec<_>>();
+        let rfr = CompoundedRfr {
+            calendar,
+            ..CompoundedRfr::new(RateIndex::new(fixings), Decimal::ZERO)
+        };
+
+        // Calendar months, and periods starting and ending at weekends and bank holidays
+        let mut periods: Vec<_> = (1..=12)
+            .map(|month| {
+                let start = ymd(2024, month, 1);
+                (start, start + Months::new(1) - Days::new(1))
+            })
+            .collect();
+        periods.extend([
+            (ymd(2024, 1, 6), ymd(2024, 2, 5)),
+            (ymd(2024, 3, 29), ymd(2024, 4, 28)),
+            (ymd(2024, 12, 25), ymd(2025, 1, 24)),
+        ]);
+
+        for (start, end) in periods {
+            for lookback_days in [0, 2, 5] {
+                for observation_shift in [false, true] {
+                    let rfr = CompoundedRfr {
+                        lookback_days,
+                        observation_shift,
+                        ..rfr.clone()
+                    };
+                    assert_eq!(
+                        rfr.compounded_rate(start, end, dec!(365)),
+                        Some(reference_rate(&rfr, start, end)),
+                        "{} to {}, lookback {}, shift {}",
+                        start,
+                        end,
+                        lookback_days,
+                        observation_shift
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_compounded_rate_over_holidays() {
+        // Good Friday and Easter Monday 2024 aren't SONIA business days, so Thursday 28 March's
+        // fixing applies for five days
+        let rfr = CompoundedRfr {
+            calendar: Calendar::EnglandAndWales,
+            ..CompoundedRfr::new(
+                RateIndex::new([
+                    (ymd(2024, 3, 27), dec!(0.0519)),
+                    (ymd(2024, 3, 28), dec!(0.0520)),
+                    (ymd(2024, 4, 2), dec!(0.0518)),
+                ]),
+                Decimal::ZERO,
+            )
+        };
+
+        // (1 + 0.0519/365)(1 + 5 * 0.052/365)(1 + 0.0518/365) - 1, over 7 days
+        assert_eq!(
+            rfr.compounded_rate(ymd(2024, 3, 27), ymd(2024, 4, 2), dec!(365)),
+            Some(dec!(0.0519687))
+        );
+    }
+
+    #[test]
+    fn test_compounded_rate_lookback_and_observation_shift() {
+        // A month of SONIA across the August 2024 Bank Rate cut, taken as 5.20% until Thursday
+        // 1 August and 4.95% from Friday 2 August, with the Sterling Working Group's five
+        // business day lag and the summer bank holiday on 26 August
+        let rfr = CompoundedRfr {
+            lookback_days: 5,
+            calendar: Calendar::EnglandAndWales,
+            ..CompoundedRfr::new(
+                RateIndex::new([
+                    (ymd(2024, 7, 1), dec!(0.0520)),
+                    (ymd(2024, 8, 2), dec!(0.0495)),
+                ]),
+                Decimal::ZERO,
+            )
+        };
+        let interest = |rate: Decimal| {
+            round_decimal(
+                dec!(1000000) * rate * dec!(31) / dec!(365),
+                None,
+                None,
+                None,
+            )
+        };
+
+        // Without a shift 1 to 8 August look back to 5.20% fixings, weighted by the interest
+        // period's days, so Friday 23 August's lagged fixing counts for the four days of the
+        // bank holiday weekend
+        let lookback = rfr
+            .compounded_rate(ymd(2024, 8, 1), ymd(2024, 8, 31), dec!(365))
+            .unwrap();
+        assert_eq!(rfr.first_fixing_date(ymd(2024, 8, 1)), ymd(2024, 7, 25));
+        assert_eq!(lookback, dec!(0.0502451));
+        assert_eq!(interest(lookback), dec!(4267.39));
+
+        // With an observation shift the fixings of 25 July to 22 August are weighted by their
+        // own days and annualised over those 29 days, so the eight days at 5.20% weigh more.
+        // Interest still accrues over the 31 days of the interest period
+        let shift = CompoundedRfr {
+            observation_shift: true,
+            ..rfr
+        };
+        let shifted = shift
+            .compounded_rate(ymd(2024, 8, 1), ymd(2024, 8, 31), dec!(365))
+            .unwrap();
+        assert_eq!(shift.first_fixing_date(ymd(2024, 8, 1)), ymd(2024, 7, 25));
+        assert_eq!(shifted, dec!(0.0502835));
+        assert_eq!(interest(shifted), dec!(4270.65));
+    }
+}
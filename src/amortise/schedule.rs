@@ -1,9 +1,15 @@
-use super::interest::{
-    calculate_period_interest, decompound_rate, get_daily_interest_rate, InterestMethod,
-    InterestType,
-};
+use super::day_count::AccrualPeriod;
+use super::deal::MotorFinanceDeal;
+use super::error::AmortisationError;
+use super::frequency::{DateGenerator, PaymentFrequency};
+use super::interest::{calculate_period_interest, get_daily_interest_rate};
+use super::rate::{annual_rate_on, DailyRates, RateChange, RateChangePayment};
+use super::solver::SolverReport;
+use super::spec::LoanSpec;
+use super::style::AmortisationStyle;
 use super::utils::round_decimal;
-use chrono::{Days, Months, NaiveDate};
+use super::{annuity_payment, weighted_annuity_payment};
+use chrono::{Days, NaiveDate};
 
 use rust_decimal::{Decimal, MathematicalOps};
 use serde::Serialize;
@@ -11,6 +17,16 @@ use serde::Serialize;
 #[derive(Debug, Serialize, Clone)]
 pub struct Payment {
     pub month: u32,
+    /// The date the payment is due, after any business day adjustment.
+    pub due_date: NaiveDate,
+    /// First day interest accrues for this payment.
+    pub accrual_start: NaiveDate,
+    /// Last day interest accrues for this payment, the capitalisation date.
+    pub accrual_end: NaiveDate,
+    /// Balance before the period's interest and payment.
+    pub opening_balance: Decimal,
+    /// Nominal annual rate in effect at the start of the accrual period.
+    pub annual_rate: Decimal,
     pub payment: Decimal,
     pub principal: Decimal,
     pub interest: Decimal,
@@ -20,13 +36,31 @@ pub struct Payment {
 #[derive(Debug, Serialize)]
 
 pub struct Meta {
+    /// Number of regular payments in the schedule, after any advance payment.
+    pub term: u32,
+    pub balloon_payment: Option<Decimal>,
     pub total_payable: Decimal,
     pub total_principal: Decimal,
     pub total_interest: Decimal,
+    /// The principal, derived from the deal when there is one.
+    pub amount_of_credit: Decimal,
+    /// The deal's total deposit paid by the customer, zero without a deal.
+    pub total_deposit: Decimal,
+    /// The dealer's deposit contribution, zero without a deal.
+    pub dealer_deposit_contribution: Decimal,
+    /// Interest and fees: the total payable less the amount of credit.
+    pub total_charge_for_credit: Decimal,
+    /// What the customer pays: the total payable plus the total deposit, which is the cash price
+    /// less the dealer's deposit contribution plus the total charge for credit for a deal.
+    pub total_amount_payable: Decimal,
+    pub deal: Option<MotorFinanceDeal>,
     pub daily_rate: Decimal,
     pub annual_rate: Decimal,
     pub calculated_apr: Decimal,
     pub calculated_ear: Decimal,
+    /// How the payment or solved parameter was found; `None` when nothing was solved for
+    /// iteratively.
+    pub solver: Option<SolverReport>,
 }
 #[derive(Debug, Serialize)]
 pub struct Schedule {
@@ -34,62 +68,194 @@ pub struct Schedule {
     pub meta: Meta,
 }
 
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Schedule {
     pub fn new() -> Self {
         Schedule {
             payments: Vec::new(),
             meta: Meta {
+                term: 0,
+                balloon_payment: None,
                 total_payable: Decimal::from(0),
                 total_principal: Decimal::from(0),
                 total_interest: Decimal::from(0),
+                amount_of_credit: Decimal::from(0),
+                total_deposit: Decimal::from(0),
+                dealer_deposit_contribution: Decimal::from(0),
+                total_charge_for_credit: Decimal::from(0),
+                total_amount_payable: Decimal::from(0),
+                deal: None,
                 daily_rate: Decimal::from(0),
                 annual_rate: Decimal::from(0),
                 calculated_apr: Decimal::from(0),
                 calculated_ear: Decimal::from(0),
+                solver: None,
             },
         }
     }
 }
 
-pub fn build_schedule(
-    principal: Decimal,
-    disbursal_date: NaiveDate,
-    first_capitalisation_date: NaiveDate,
-    first_payment_date: NaiveDate,
-    num_payments: u32,
-    annual_rate: Decimal,
-    period_payment: Decimal,
-    interest_method: InterestMethod,
-    interest_type: InterestType,
-    settle_balance: bool,
-    balloon_payment: Option<Decimal>,
-    option_fee: Option<Decimal>,
-) -> Schedule {
+pub fn build_schedule(spec: &LoanSpec, period_payment: Decimal, settle_balance: bool) -> Schedule {
     let mut schedule = Schedule::new();
+    let num_payments = spec.num_payments;
+    schedule.meta.term = num_payments;
+    schedule.meta.balloon_payment = spec.balloon_payment;
+    let day_counter = spec.day_counter();
+    let balloon_payment = spec.balloon_payment;
+    let option_fee = spec.option_fee;
+    let frequency = spec.frequency;
+    let calendar = &spec.calendar;
+    let convention = spec.business_day_convention;
+    // Capitalisation dates are only moved when interest accrues to the adjusted dates
+    let accrual_date = |date| {
+        if spec.accrue_to_adjusted_dates {
+            convention.adjust(date, calendar)
+        } else {
+            date
+        }
+    };
 
-    if interest_type == InterestType::Compound {
-        schedule.meta.annual_rate = decompound_rate(annual_rate);
-    } else {
-        schedule.meta.annual_rate = annual_rate;
-    }
+    let mut balance = spec.principal;
+    let mut interest_payable_from = spec.disbursal_date;
+    let cap_dates = DateGenerator::new(spec.capitalisation_date(), frequency, spec.end_of_month);
+    let payment_dates = DateGenerator::new(spec.first_payment_date, frequency, spec.end_of_month);
+    let maturity = accrual_date(cap_dates.date(num_payments.saturating_sub(1)));
 
-    let daily_rate = get_daily_interest_rate(schedule.meta.annual_rate, interest_method);
-    schedule.meta.daily_rate = daily_rate;
+    schedule.meta.annual_rate = spec.nominal_rate();
 
-    let mut balance = principal;
-    let mut interest_payable_from = disbursal_date;
-    let mut next_cap_date = first_capitalisation_date;
-    let mut next_payment_date = first_payment_date;
+    let daily_rate = get_daily_interest_rate(schedule.meta.annual_rate, day_counter);
+    schedule.meta.daily_rate = daily_rate;
+    let rate_changes: Vec<_> = spec
+        .rate_schedule(maturity)
+        .into_iter()
+        .map(|change| RateChange {
+            annual_rate: spec.nominalise(change.annual_rate),
+            ..change
+        })
+        .collect();
+    let daily_rates = DailyRates::new(
+        daily_rate,
+        rate_changes.iter().map(|change| {
+            (
+                change.effective_date,
+                get_daily_interest_rate(change.annual_rate, day_counter),
+            )
+        }),
+    );
+    // An equal principal loan's instalment doesn't depend on the rate
+    let equal_principal = spec.amortisation_style == AmortisationStyle::EqualPrincipal;
+    let recast = spec.rate_change_payment == RateChangePayment::Recast && !equal_principal;
+    let mut period_payment = period_payment;
+
+    if spec.advance_payments > 0 {
+        // Advance payments are collected on the disbursal date, before any interest accrues,
+        // so interest only ever runs on the reduced balance
+        let payment = round_decimal(
+            period_payment * Decimal::from(spec.advance_payments),
+            None,
+            None,
+            None,
+        );
+        let opening_balance = balance;
+        balance -= payment;
+        schedule.payments.push(Payment {
+            month: 0,
+            due_date: spec.disbursal_date,
+            accrual_start: spec.disbursal_date,
+            accrual_end: spec.disbursal_date,
+            opening_balance,
+            annual_rate: annual_rate_on(
+                schedule.meta.annual_rate,
+                &rate_changes,
+                spec.disbursal_date,
+            ),
+            payment,
+            principal: payment,
+            interest: Decimal::ZERO,
+            balance,
+            days: 0,
+        });
+        schedule.meta.total_payable += payment;
+        schedule.meta.total_principal += payment;
+    }
 
     for month in 1..=num_payments {
+        // Dates are generated unadjusted so adjustments don't accumulate
+        let accrual_end = accrual_date(cap_dates.date(month - 1));
+        let due_date = convention.adjust(payment_dates.date(month - 1), calendar);
+        let interest_only = month <= spec.interest_only_periods;
+        if recast && daily_rates.changes_between(interest_payable_from, accrual_end) {
+            period_payment = recast_payment(
+                spec,
+                balance,
+                daily_rates.rate_on(accrual_end),
+                month,
+                num_payments,
+            )
+            // Keep the payment if the new rate overflows the annuity; the final payment
+            // still settles the balance
+            .unwrap_or(period_payment);
+        }
+        // A compounded rate is only known from the period's own fixings, so each period
+        // accrues at a flat rate of its own
+        let period_rates;
+        let (rates, annual_rate) = match &spec.compounded_rfr {
+            Some(rfr) => {
+                let compounded_rate = rfr
+                    .compounded_rate(
+                        interest_payable_from,
+                        accrual_end,
+                        day_counter.days_in_year(),
+                    )
+                    // validate checks there's a fixing for the first day, and later days
+                    // fall back on the latest fixing
+                    .unwrap_or_default();
+                let annual_rate = compounded_rate + rfr.margin;
+                period_rates = DailyRates::flat(get_daily_interest_rate(annual_rate, day_counter));
+                (&period_rates, annual_rate)
+            }
+            None => (
+                &daily_rates,
+                annual_rate_on(
+                    schedule.meta.annual_rate,
+                    &rate_changes,
+                    interest_payable_from,
+                ),
+            ),
+        };
+        // The scheduled payment, which reduces the balance from the due date. Interest-only
+        // payments leave the balance where it is, and an equal principal loan's interest is
+        // added to its instalment once it's known
+        let scheduled_payment = if interest_only {
+            Decimal::ZERO
+        } else if equal_principal {
+            period_payment
+        } else {
+            round_decimal(
+                period_payment * spec.payment_weight(month),
+                None,
+                None,
+                None,
+            )
+        };
         let (interest, days) = calculate_period_interest(
-            interest_payable_from,
-            next_cap_date,
-            next_payment_date,
-            daily_rate,
+            &AccrualPeriod {
+                start: interest_payable_from,
+                end: accrual_end,
+                payment_date: due_date,
+                maturity,
+                frequency,
+                calendar,
+            },
+            rates,
             balance,
-            period_payment,
-            interest_method,
+            scheduled_payment,
+            day_counter,
         );
         let mut principal_payment;
         let mut payment;
@@ -101,22 +267,26 @@ pub fn build_schedule(
             if let Some(fee) = option_fee {
                 payment += fee;
             }
-        } else if month == num_payments && balloon_payment.is_some() {
+        } else if let (true, Some(balloon)) = (month == num_payments, balloon_payment) {
             // Final payment with balloon payment - the payment IS the balloon payment amount
-            payment = balloon_payment.unwrap();
+            payment = balloon;
             // Add option fee to balloon payment if present
             if let Some(fee) = option_fee {
                 payment += fee;
             }
+        } else if interest_only {
+            payment = interest;
+        } else if equal_principal {
+            payment = period_payment + interest;
         } else {
-            payment = period_payment;
+            payment = scheduled_payment;
         }
 
         principal_payment = round_decimal(payment - interest, None, None, None);
         
         // Adjust principal payment to exclude option fee
-        if month == num_payments && option_fee.is_some() {
-            principal_payment = round_decimal(principal_payment - option_fee.unwrap(), None, None, None);
+        if let (true, Some(fee)) = (month == num_payments, option_fee) {
+            principal_payment = round_decimal(principal_payment - fee, None, None, None);
         }
         
         // For balloon payments, adjust the principal payment calculation
@@ -128,11 +298,17 @@ pub fn build_schedule(
             // we track principal and interest separately
         }
 
+        let opening_balance = balance;
         balance = round_decimal(balance - principal_payment, None, None, None);
 
         schedule.payments.push(Payment {
             month,
-            payment: payment,
+            due_date,
+            accrual_start: interest_payable_from,
+            accrual_end,
+            opening_balance,
+            annual_rate,
+            payment,
             principal: principal_payment,
             interest,
             balance,
@@ -142,18 +318,61 @@ pub fn build_schedule(
         schedule.meta.total_principal += principal_payment;
         schedule.meta.total_interest += interest;
 
-        interest_payable_from = next_cap_date + Days::new(1);
-        next_cap_date = next_cap_date + Months::new(1);
-        next_payment_date = next_payment_date + Months::new(1);
+        interest_payable_from = accrual_end + Days::new(1);
     }
 
-    schedule.meta.calculated_apr = get_apr(schedule.payments.clone());
+    schedule.meta.amount_of_credit = spec.principal;
+    schedule.meta.total_deposit = spec
+        .deal
+        .as_ref()
+        .map_or(Decimal::ZERO, MotorFinanceDeal::total_deposit);
+    schedule.meta.dealer_deposit_contribution = spec
+        .deal
+        .as_ref()
+        .map_or(Decimal::ZERO, |deal| deal.dealer_deposit_contribution);
+    schedule.meta.total_charge_for_credit = schedule.meta.total_payable - spec.principal;
+    schedule.meta.total_amount_payable = schedule.meta.total_payable + schedule.meta.total_deposit;
+    schedule.meta.deal = spec.deal.clone();
+
+    schedule.meta.calculated_apr = get_apr(&schedule.payments, frequency);
     schedule.meta.calculated_ear = schedule.meta.calculated_apr; // TODO: revise once fees are added
 
     schedule
 }
 
-fn get_apr(payments: Vec<Payment>) -> Decimal {
+/// The payment that repays `balance` over the payments from `month` to `num_payments` at
+/// `daily_rate`, leaving any balloon payment outstanding. With a payment profile this is the
+/// base payment the remaining weights apply to.
+fn recast_payment(
+    spec: &LoanSpec,
+    balance: Decimal,
+    daily_rate: Decimal,
+    month: u32,
+    num_payments: u32,
+) -> Result<Decimal, AmortisationError> {
+    let first_amortising = month.max(spec.interest_only_periods + 1);
+    let day_counter = spec.day_counter();
+    let period_rate = match day_counter.fixed_period_days(spec.frequency) {
+        Some(days) => daily_rate * Decimal::from(days),
+        None => {
+            daily_rate * day_counter.days_in_year()
+                / Decimal::from(spec.frequency.periods_per_year())
+        }
+    };
+    let balloon = spec.balloon_payment.unwrap_or_default();
+    if spec.payment_profile.is_some() {
+        let weights = (first_amortising..=num_payments).map(|payment| spec.payment_weight(payment));
+        return weighted_annuity_payment(balance, balloon, period_rate, Decimal::ZERO, weights);
+    }
+    annuity_payment(
+        balance,
+        balloon,
+        period_rate,
+        num_payments - first_amortising + 1,
+    )
+}
+
+fn get_apr(payments: &[Payment], frequency: PaymentFrequency) -> Decimal {
     let mut balance_curve = Decimal::from(0);
     let mut total_interest = Decimal::from(0);
 
@@ -162,8 +381,12 @@ fn get_apr(payments: Vec<Payment>) -> Decimal {
         total_interest += payment.interest;
     }
 
+    // A loan repaid in full with its only payment leaves no balance to build a curve from
+    if balance_curve.is_zero() {
+        return get_single_payment_apr(payments);
+    }
     let daily_cost = total_interest / balance_curve;
-    let payments_per_year = Decimal::from(12);
+    let payments_per_year = Decimal::from(frequency.periods_per_year());
 
     let apr = (Decimal::ONE + daily_cost * Decimal::from(365) / payments_per_year)
         .powd(payments_per_year)
@@ -172,9 +395,31 @@ fn get_apr(payments: Vec<Payment>) -> Decimal {
     round_decimal(apr, None, Some(6), None)
 }
 
+/// The APR straight from the cash flows: the amount lent grows to the amount repaid over the
+/// days between them.
+fn get_single_payment_apr(payments: &[Payment]) -> Decimal {
+    let (Some(first), Some(last)) = (payments.first(), payments.last()) else {
+        return Decimal::ZERO;
+    };
+    let days = (last.due_date - first.accrual_start).num_days();
+    let repaid: Decimal = payments.iter().map(|payment| payment.payment).sum();
+    let Some(growth) = repaid.checked_div(first.opening_balance) else {
+        return Decimal::ZERO;
+    };
+    if days <= 0 {
+        return Decimal::ZERO;
+    }
+
+    let apr = growth.powd(Decimal::from(365) / Decimal::from(days)) - Decimal::ONE;
+
+    round_decimal(apr, None, Some(6), None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::amortise::{BusinessDayConvention, Calendar, InterestMethod, InterestType};
+    use chrono::NaiveDate;
     use rust_decimal_macros::dec;
     use std::str::FromStr;
 
@@ -190,24 +435,23 @@ mod tests {
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
         let first_payment_date = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
 
-        let schedule = build_schedule(
+        let spec = LoanSpec::builder(
             principal,
+            annual_rate,
+            num_payments,
             disbursal_date,
-            first_capitalisation_date,
             first_payment_date,
-            num_payments,
-            annual_rate,
-            period_payment,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            true,
-            None, // no balloon payment
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .interest_method(InterestMethod::ActualActualIsda)
+        .interest_type(InterestType::Simple)
+        .build();
+
+        let schedule = build_schedule(&spec, period_payment, true);
 
-        let apr = get_apr(schedule.payments);
+        let apr = get_apr(&schedule.payments, PaymentFrequency::Monthly);
 
-        assert_eq!(apr, dec!(0.053803));
+        assert_eq!(apr, dec!(0.054629));
     }
 
     #[test]
@@ -222,26 +466,36 @@ mod tests {
         let first_capitalisation_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
         let first_payment_date = NaiveDate::from_ymd_opt(2023, 3, 1).unwrap();
 
-        let schedule = build_schedule(
+        let spec = LoanSpec::builder(
             principal,
+            annual_rate,
+            num_payments,
             disbursal_date,
-            first_capitalisation_date,
             first_payment_date,
-            num_payments,
-            annual_rate,
-            period_payment,
-            InterestMethod::ActualActual,
-            InterestType::Simple,
-            true,
-            None, // no balloon payment
-            None, // no option fee
-        );
+        )
+        .first_capitalisation_date(first_capitalisation_date)
+        .interest_method(InterestMethod::ActualActualIsda)
+        .interest_type(InterestType::Simple)
+        .build();
+
+        let schedule = build_schedule(&spec, period_payment, true);
 
         assert_eq!(schedule.payments.len(), 36);
         assert_eq!(schedule.payments.last().unwrap().balance, Decimal::from(0));
+        // Periods run on from one another, each opening on the last one's closing balance
+        let first = &schedule.payments[0];
+        assert_eq!(first.opening_balance, principal);
+        assert_eq!(first.accrual_start, disbursal_date);
+        assert_eq!(first.accrual_end, first_capitalisation_date);
+        assert_eq!(first.due_date, first_payment_date);
+        for pair in schedule.payments.windows(2) {
+            assert_eq!(pair[1].opening_balance, pair[0].balance);
+            assert_eq!(pair[1].accrual_start, pair[0].accrual_end + Days::new(1));
+        }
+        // Spans 2024, where every day accrues at 1/366 of the annual rate
         assert_eq!(
             schedule.meta.total_payable,
-            Decimal::from_str("17073.12").unwrap()
+            Decimal::from_str("17105.59").unwrap()
         );
         assert_eq!(
             schedule.meta.total_principal,
@@ -249,7 +503,147 @@ mod tests {
         );
         assert_eq!(
             schedule.meta.total_interest,
-            Decimal::from_str("2073.12").unwrap()
+            Decimal::from_str("2105.59").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_schedule_broken_first_period_30_360() {
+        let spec = LoanSpec::builder(
+            dec!(12000),
+            dec!(0.06),
+            12,
+            NaiveDate::from_ymd_opt(2023, 1, 17).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+        );
+
+        let fixed = build_schedule(
+            &spec
+                .clone()
+                .interest_method(InterestMethod::Convention30_360)
+                .build(),
+            dec!(1000),
+            true,
+        );
+        assert_eq!(fixed.payments[0].days, 30);
+        assert_eq!(fixed.payments[0].interest, dec!(60.00));
+
+        for interest_method in [
+            InterestMethod::Convention30_360Us,
+            InterestMethod::Convention30E360,
+            InterestMethod::Convention30E360Isda,
+            InterestMethod::Convention30_360German,
+        ] {
+            let schedule = build_schedule(
+                &spec.clone().interest_method(interest_method).build(),
+                dec!(1000),
+                true,
+            );
+
+            // 17 January to 1 February, counting the disbursal day, is about half a month. The
+            // 30E conventions count 1 February rather than 31 January, after the payment.
+            let first = &schedule.payments[0];
+            assert_eq!(first.days, 15, "{:?}", interest_method);
+            assert!(
+                (first.interest - dec!(30)).abs() < dec!(0.5),
+                "{:?}",
+                interest_method
+            );
+            assert_eq!(schedule.payments[1].days, 30, "{:?}", interest_method);
+        }
+    }
+
+    #[test]
+    fn test_build_schedule_business_day_adjustment() {
+        let spec = LoanSpec::builder(
+            dec!(12000),
+            dec!(0.06),
+            12,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Actual365)
+        .calendar(Calendar::EnglandAndWales);
+        let days = |schedule: &Schedule| -> Vec<u32> {
+            schedule.payments.iter().take(6).map(|p| p.days).collect()
+        };
+
+        let unadjusted = build_schedule(&spec.clone().build(), dec!(1000), true);
+        assert_eq!(days(&unadjusted), vec![32, 29, 31, 30, 31, 30]);
+
+        // 1 April is Easter Monday and 1 June a Saturday, so those periods run on to the next
+        // business day and the following periods are shorter
+        let adjusted = build_schedule(
+            &spec
+                .clone()
+                .business_day_convention(BusinessDayConvention::Following)
+                .accrue_to_adjusted_dates(true)
+                .build(),
+            dec!(1000),
+            true,
+        );
+        assert_eq!(days(&adjusted), vec![32, 29, 32, 29, 33, 28]);
+        assert_eq!(adjusted.meta.total_principal, dec!(12000));
+
+        // Accruing to the unadjusted dates keeps the periods, but the payments now land after
+        // the capitalisation date, so those periods accrue on the full balance to the end
+        let rolled = build_schedule(
+            &spec
+                .business_day_convention(BusinessDayConvention::Following)
+                .build(),
+            dec!(1000),
+            true,
+        );
+        assert_eq!(days(&rolled), days(&unadjusted));
+        assert_eq!(rolled.payments[0].interest, unadjusted.payments[0].interest);
+        assert!(rolled.payments[2].interest > unadjusted.payments[2].interest);
+        assert!(rolled.payments[4].interest > unadjusted.payments[4].interest);
+    }
+
+    #[test]
+    fn test_build_schedule_dates_from_anchor() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let spec = LoanSpec::builder(
+            dec!(6000),
+            dec!(0.06),
+            6,
+            date(2023, 12, 31),
+            date(2024, 1, 31),
+        )
+        .interest_method(InterestMethod::Actual365);
+        let due_dates = |schedule: &Schedule| -> Vec<NaiveDate> {
+            schedule.payments.iter().map(|p| p.due_date).collect()
+        };
+
+        // Back to the 31st after February
+        let schedule = build_schedule(&spec.clone().build(), dec!(1000), true);
+        assert_eq!(
+            due_dates(&schedule),
+            vec![
+                date(2024, 1, 31),
+                date(2024, 2, 29),
+                date(2024, 3, 31),
+                date(2024, 4, 30),
+                date(2024, 5, 31),
+                date(2024, 6, 30),
+            ]
+        );
+        assert_eq!(schedule.payments[2].days, 31);
+        assert_eq!(schedule.payments[0].accrual_start, date(2023, 12, 31));
+        assert_eq!(schedule.payments[2].accrual_start, date(2024, 3, 1));
+        assert_eq!(schedule.payments[2].accrual_end, date(2024, 3, 31));
+
+        // The end-of-month rule pins a mid-month anchor to month ends
+        let schedule = build_schedule(
+            &LoanSpec {
+                first_payment_date: date(2024, 1, 15),
+                ..spec.end_of_month(true).build()
+            },
+            dec!(1000),
+            true,
         );
+        assert_eq!(schedule.payments[0].due_date, date(2024, 1, 31));
+        assert_eq!(schedule.payments[1].due_date, date(2024, 2, 29));
+        assert_eq!(schedule.payments[5].due_date, date(2024, 6, 30));
     }
 }
@@ -1,48 +1,71 @@
+use super::error::AmortisationError;
+use super::solver::Root;
 use rust_decimal::Decimal;
 
+/// Secant method root finder. Converges when the residual is under `tolerance` or the step
+/// is under `x_tolerance`, and gives up after `max_iterations` evaluations of `f`.
 pub fn secant_method<F>(
-    f: F,
+    mut f: F,
     x0: Decimal,
     x1: Decimal,
-    epsilon: Decimal,
-    max_iterations: usize,
-) -> Option<Decimal>
+    tolerance: Decimal,
+    x_tolerance: Decimal,
+    max_iterations: u32,
+) -> Result<Root, AmortisationError>
 where
-    F: Fn(Decimal) -> Decimal,
+    F: FnMut(Decimal) -> Result<Decimal, AmortisationError>,
 {
     let mut x0 = x0;
     let mut x1 = x1;
-    let mut iteration = 0;
+    let mut f0 = f(x0)?;
+    let mut f1 = f(x1)?;
+    let mut iteration = 2;
 
     while iteration < max_iterations {
-        let f0 = f(x0);
-        let f1 = f(x1);
-
         // Check if we've found a root
-        if f1.abs() < epsilon {
-            return Some(x1);
+        if f1.abs() < tolerance {
+            return Ok(Root {
+                value: x1,
+                residual: f1,
+                iterations: iteration,
+            });
         }
 
-        // Avoid division by zero
+        // A flat secant gives no direction to step in
         if f1 == f0 {
-            return Some(x1);
+            break;
         }
         // Calculate the next x value
         let x2 = x1 - f1 * (x1 - x0) / (f1 - f0);
+        let f2 = f(x2)?;
+        iteration += 1;
 
         // Check for convergence
-        if (x2 - x1).abs() < epsilon {
-            return Some(x2);
+        if (x2 - x1).abs() < x_tolerance {
+            return Ok(Root {
+                value: x2,
+                residual: f2,
+                iterations: iteration,
+            });
         }
 
         // Update values for next iteration
         x0 = x1;
+        f0 = f1;
         x1 = x2;
-        iteration += 1;
+        f1 = f2;
+    }
+
+    if f1.abs() < tolerance {
+        return Ok(Root {
+            value: x1,
+            residual: f1,
+            iterations: iteration,
+        });
     }
 
     // If we've reached here, the method didn't converge
-    None
+    Err(AmortisationError::NonConvergence { last_iterate: x1 })
 }
 
 #[cfg(test)]
@@ -52,42 +75,68 @@ mod tests {
 
     #[test]
     fn test_secant_method_converges() {
-        let f = |x: Decimal| x * x - dec!(2);
-        let root = secant_method(f, dec!(1), dec!(2), dec!(0.0001), 100);
-        assert!(root.is_some());
+        let f = |x: Decimal| Ok(x * x - dec!(2));
+        let root = secant_method(f, dec!(1), dec!(2), dec!(0.0001), dec!(0.0001), 100);
+        assert!(root.is_ok());
         let root = root.unwrap();
-        assert!((root - dec!(1.4142)).abs() < dec!(0.0001));
+        assert!((root.value - dec!(1.4142)).abs() < dec!(0.0001));
     }
 
     #[test]
     fn test_secant_method_no_convergence() {
-        let f = |x: Decimal| x * x + dec!(1);
-        let root = secant_method(f, dec!(1), dec!(0), dec!(0.0001), 100);
-        assert!(root.unwrap() == dec!(1));
+        let f = |x: Decimal| Ok(x * x + dec!(1));
+        let root = secant_method(f, dec!(1), dec!(0), dec!(0.0001), dec!(0.0001), 100);
+        assert!(matches!(
+            root,
+            Err(AmortisationError::NonConvergence { .. })
+        ));
+    }
+
+    #[test]
+    fn test_secant_method_iteration_limit() {
+        let f = |x: Decimal| Ok(x * x - dec!(2));
+        let result = secant_method(f, dec!(1), dec!(100), dec!(0.0001), dec!(0.0001), 3);
+        assert!(matches!(
+            result,
+            Err(AmortisationError::NonConvergence { .. })
+        ));
     }
 
     #[test]
     fn test_secant_method_zero_derivative() {
-        let f = |x: Decimal| x * x;
-        let root = secant_method(f, dec!(1), dec!(1), dec!(0.0001), 100);
-        assert!(root.unwrap() == dec!(1));
+        let f = |x: Decimal| Ok(x * x);
+        let root = secant_method(f, dec!(1), dec!(1), dec!(0.0001), dec!(0.0001), 100);
+        assert_eq!(
+            root,
+            Err(AmortisationError::NonConvergence {
+                last_iterate: dec!(1)
+            })
+        );
     }
 
     #[test]
     fn test_secant_method_linear_function() {
-        let f = |x: Decimal| x - dec!(5);
-        let root = secant_method(f, dec!(0), dec!(10), dec!(0.0001), 100);
-        assert!(root.is_some());
+        let f = |x: Decimal| Ok(x - dec!(5));
+        let root = secant_method(f, dec!(0), dec!(10), dec!(0.0001), dec!(0.0001), 100);
+        assert!(root.is_ok());
         let root = root.unwrap();
-        assert!((root - dec!(5)).abs() < dec!(0.0001));
+        assert!((root.value - dec!(5)).abs() < dec!(0.0001));
+        assert_eq!(root.iterations, 3);
     }
 
     #[test]
     fn test_secant_method_high_precision() {
-        let f = |x: Decimal| x * x - dec!(2);
-        let root = secant_method(f, dec!(1), dec!(2), dec!(0.00000001), 1000);
-        assert!(root.is_some());
+        let f = |x: Decimal| Ok(x * x - dec!(2));
+        let root = secant_method(
+            f,
+            dec!(1),
+            dec!(2),
+            dec!(0.00000001),
+            dec!(0.00000001),
+            1000,
+        );
+        assert!(root.is_ok());
         let root = root.unwrap();
-        assert!((root - dec!(1.41421356)).abs() < dec!(0.00000001));
+        assert!((root.value - dec!(1.41421356)).abs() < dec!(0.00000001));
     }
 }
@@ -0,0 +1,537 @@
+use super::error::AmortisationError;
+use super::interest::InterestType;
+use super::rate::RateChangePayment;
+use super::residual;
+use super::schedule::{build_schedule, Schedule};
+use super::solver::find_root;
+use super::spec::LoanSpec;
+use super::style::AmortisationStyle;
+use super::utils::round_decimal;
+use rust_decimal::prelude::RoundingStrategy;
+use rust_decimal::Decimal;
+
+const RATE_SCALE: u32 = 6;
+const RATE_X_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 9);
+const PRINCIPAL_X_TOLERANCE: Decimal = Decimal::from_parts(1, 0, 0, false, 3);
+const MAX_TERM_YEARS: u32 = 100;
+
+impl LoanSpec {
+    /// The fixed payment the solvers work from. Interest-only and equal principal loans have
+    /// no fixed payment to solve from.
+    fn solvable_payment(&self) -> Result<Decimal, AmortisationError> {
+        if self.is_interest_only() {
+            return Err(AmortisationError::Unsupported(
+                "solving for a parameter of an interest-only loan",
+            ));
+        }
+        if self.amortisation_style == AmortisationStyle::EqualPrincipal {
+            return Err(AmortisationError::Unsupported(
+                "solving for a parameter of an equal principal loan",
+            ));
+        }
+        self.fixed_payment
+            .ok_or(AmortisationError::MissingFixedPayment)
+    }
+
+    /// Finds the nominal annual rate at which `fixed_payment` amortises the loan to zero, or to
+    /// the balloon payment when one is set.
+    ///
+    /// The returned schedule is built at the solved rate, reported as `meta.annual_rate`. Only
+    /// `Simple` loans can be solved for, as the nominal rate is what's found, and payments that
+    /// don't repay more than the principal give a `NonPositiveRate` error.
+    pub fn solve_rate(&self) -> Result<Schedule, AmortisationError> {
+        let payment = self.solvable_payment()?;
+        if self.floating_rate.is_some() || self.compounded_rfr.is_some() {
+            return Err(AmortisationError::Unsupported(
+                "solving for the rate of a floating rate loan",
+            ));
+        }
+        if self.interest_type == InterestType::Compound {
+            return Err(AmortisationError::Unsupported(
+                "solving for the rate of a Compound interest loan",
+            ));
+        }
+        self.validate()?;
+
+        let mut iteration_spec = self.iteration_spec();
+        let f = |annual_rate| {
+            iteration_spec.annual_rate = annual_rate;
+            residual(&iteration_spec, payment, self.balloon_payment)
+        };
+
+        let root = find_root(
+            f,
+            Decimal::new(5, 2),
+            Decimal::new(10, 2),
+            &self.solver,
+            RATE_X_TOLERANCE,
+        )?;
+
+        let annual_rate = round_decimal(root.value, None, Some(RATE_SCALE), None);
+        if annual_rate <= Decimal::ZERO {
+            return Err(AmortisationError::NonPositiveRate(annual_rate));
+        }
+        let solved_spec = LoanSpec {
+            annual_rate,
+            ..self.clone()
+        };
+        let mut schedule = build_schedule(&solved_spec, payment, self.balloon_payment.is_none());
+        schedule.meta.solver = Some(root.into());
+        Ok(schedule)
+    }
+
+    /// Finds how many payments of `fixed_payment` are needed to repay the loan.
+    ///
+    /// `num_payments` is ignored. The schedule stops at the payment that clears the balance,
+    /// which is reduced to whatever is left owing, and `meta.term` holds the number of payments.
+    pub fn solve_term(&self) -> Result<Schedule, AmortisationError> {
+        // The term is what's being found, so any interest-only periods just come first
+        let payment = self
+            .fixed_payment
+            .ok_or(AmortisationError::MissingFixedPayment)?;
+        if self.balloon_payment.is_some() {
+            return Err(AmortisationError::Unsupported(
+                "solving for the term of a loan with a balloon payment",
+            ));
+        }
+
+        self.repay_with(payment)
+    }
+
+    /// Pays `payment` every period until the loan is repaid, with the final payment reduced to
+    /// whatever is left owing. Rate changes never recast the payment.
+    pub(super) fn repay_with(&self, payment: Decimal) -> Result<Schedule, AmortisationError> {
+        let horizon_spec = LoanSpec {
+            num_payments: MAX_TERM_YEARS * self.frequency.periods_per_year(),
+            rate_change_payment: RateChangePayment::FlexTerm,
+            ..self.iteration_spec()
+        };
+        horizon_spec.validate()?;
+
+        let horizon = build_schedule(&horizon_spec, payment, false);
+        // Rows are numbered from the first regular payment, after any advance payment
+        let term = horizon
+            .payments
+            .iter()
+            .find(|p| p.balance <= Decimal::ZERO)
+            .ok_or(AmortisationError::PaymentDoesNotAmortise { payment })?
+            .month;
+
+        let solved_spec = LoanSpec {
+            num_payments: term,
+            rate_change_payment: RateChangePayment::FlexTerm,
+            ..self.clone()
+        };
+        Ok(build_schedule(&solved_spec, payment, true))
+    }
+
+    /// Finds the largest principal that `fixed_payment` repays over `num_payments`, leaving the
+    /// balloon payment outstanding when one is set.
+    ///
+    /// `principal` is ignored. The answer is rounded down to the penny, so the final payment may
+    /// be slightly smaller than the others.
+    pub fn solve_principal(&self) -> Result<Schedule, AmortisationError> {
+        let payment = self.solvable_payment()?;
+        if self.deal.is_some() {
+            return Err(AmortisationError::Unsupported(
+                "solving for the principal of a motor finance deal",
+            ));
+        }
+
+        let mut iteration_spec = self.iteration_spec();
+        let f = |principal| {
+            iteration_spec.principal = principal;
+            residual(&iteration_spec, payment, self.balloon_payment)
+        };
+
+        // The final balance is close to linear in the principal, so any two distinct
+        // guesses converge quickly
+        let undiscounted =
+            payment * Decimal::from(self.amortising_payments() + self.advance_payments);
+        let root = find_root(
+            f,
+            undiscounted / Decimal::from(2),
+            undiscounted,
+            &self.solver,
+            PRINCIPAL_X_TOLERANCE,
+        )?;
+
+        let solved_spec = LoanSpec {
+            principal: round_decimal(root.value, None, None, Some(RoundingStrategy::ToZero)),
+            ..self.clone()
+        };
+        solved_spec.validate()?;
+        let mut schedule = build_schedule(&solved_spec, payment, self.balloon_payment.is_none());
+        schedule.meta.solver = Some(root.into());
+        Ok(schedule)
+    }
+
+    /// Finds the balloon payment (GMFV) that leaves `fixed_payment` amortising the loan over
+    /// `num_payments`, for PCP deals structured from the monthly payment.
+    ///
+    /// `balloon_payment` is ignored. If `max_balloon_ratio` is given (0.4 for 40% of principal)
+    /// and the balloon would exceed it, the balloon is capped and the payment recalculated.
+    /// The balloon used is reported in `meta.balloon_payment`.
+    pub fn solve_balloon(
+        &self,
+        max_balloon_ratio: Option<Decimal>,
+    ) -> Result<Schedule, AmortisationError> {
+        let payment = self.solvable_payment()?;
+        let unsolved_spec = LoanSpec {
+            balloon_payment: None,
+            ..self.clone()
+        };
+        unsolved_spec.validate()?;
+
+        // Whatever the regular payments leave outstanding is the balloon
+        let balloon = residual(&self.iteration_spec(), payment, None)?;
+        if balloon <= Decimal::ZERO {
+            // The payment repays the loan on its own
+            return Ok(build_schedule(&unsolved_spec, payment, true));
+        }
+
+        if let Some(ratio) = max_balloon_ratio {
+            let limit = round_decimal(
+                self.principal * ratio,
+                None,
+                None,
+                Some(RoundingStrategy::ToZero),
+            );
+            if balloon > limit {
+                return LoanSpec {
+                    fixed_payment: None,
+                    balloon_payment: Some(limit),
+                    ..self.clone()
+                }
+                .amortise();
+            }
+        }
+
+        let solved_spec = LoanSpec {
+            balloon_payment: Some(balloon),
+            ..self.clone()
+        };
+        solved_spec.validate()?;
+        Ok(build_schedule(&solved_spec, payment, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amortise::InterestMethod;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    fn builder() -> crate::amortise::LoanBuilder {
+        LoanSpec::builder(
+            dec!(18000),
+            dec!(0.079),
+            48,
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_solve_rate_round_trips_amortise() {
+        for interest_method in [
+            InterestMethod::Convention30_360,
+            InterestMethod::Actual365,
+            InterestMethod::Actual360,
+            InterestMethod::ActualActualIsda,
+            InterestMethod::ActualActualIcma,
+        ] {
+            let spec = builder().interest_method(interest_method).build();
+            let payment = spec.amortise().unwrap().payments[0].payment;
+
+            let solved = LoanSpec {
+                annual_rate: Decimal::ZERO,
+                fixed_payment: Some(payment),
+                ..spec
+            }
+            .solve_rate()
+            .unwrap();
+
+            assert!(
+                (solved.meta.annual_rate - dec!(0.079)).abs() < dec!(0.0001),
+                "{:?} solved rate {}",
+                interest_method,
+                solved.meta.annual_rate
+            );
+            assert_eq!(solved.payments[0].payment, payment);
+            assert_eq!(solved.payments.last().unwrap().balance, Decimal::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_solve_rate_rejects_compound() {
+        let spec = builder()
+            .interest_type(InterestType::Compound)
+            .fixed_payment(dec!(450))
+            .build();
+
+        assert_eq!(
+            spec.solve_rate().unwrap_err(),
+            AmortisationError::Unsupported("solving for the rate of a Compound interest loan")
+        );
+    }
+
+    #[test]
+    fn test_solve_rate_rejects_non_positive_rate() {
+        // 48 payments of £350 only repay £16,800 of the £18,000
+        let result = builder().fixed_payment(dec!(350)).build().solve_rate();
+        assert!(matches!(
+            result,
+            Err(AmortisationError::NonPositiveRate(rate)) if rate < Decimal::ZERO
+        ));
+
+        // Payments that exactly repay the principal are interest free
+        let result = builder().fixed_payment(dec!(375)).build().solve_rate();
+        assert_eq!(
+            result.unwrap_err(),
+            AmortisationError::NonPositiveRate(Decimal::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_solve_rate_with_balloon() {
+        let spec = builder()
+            .fixed_payment(dec!(250))
+            .balloon_payment(dec!(8000))
+            .build();
+
+        let solved = spec.solve_rate().unwrap();
+
+        let check = LoanSpec {
+            annual_rate: solved.meta.annual_rate,
+            fixed_payment: None,
+            ..spec
+        }
+        .amortise()
+        .unwrap();
+        assert!((check.payments[0].payment - dec!(250)).abs() < dec!(0.05));
+        assert_eq!(solved.payments.last().unwrap().payment, dec!(8000));
+        assert_eq!(solved.payments.last().unwrap().balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_solve_rate_broker_quote() {
+        // £450/month over 48 months on £18,000
+        let solved = builder()
+            .fixed_payment(dec!(450))
+            .build()
+            .solve_rate()
+            .unwrap();
+
+        assert!(
+            solved.meta.annual_rate > dec!(0.09) && solved.meta.annual_rate < dec!(0.11),
+            "solved rate {}",
+            solved.meta.annual_rate
+        );
+        assert!(solved.payments[..47].iter().all(|p| p.payment == dec!(450)));
+    }
+
+    #[test]
+    fn test_solve_rate_requires_fixed_payment() {
+        assert_eq!(
+            builder().build().solve_rate().unwrap_err(),
+            AmortisationError::MissingFixedPayment
+        );
+    }
+
+    #[test]
+    fn test_solve_term() {
+        let spec = builder().fixed_payment(dec!(450)).build();
+
+        let schedule = spec.solve_term().unwrap();
+
+        assert_eq!(schedule.meta.term, 47);
+        assert_eq!(schedule.payments.len(), 47);
+        assert!(schedule.payments[..46]
+            .iter()
+            .all(|p| p.payment == dec!(450)));
+        let last = schedule.payments.last().unwrap();
+        assert!(last.payment > Decimal::ZERO && last.payment < dec!(450));
+        assert_eq!(last.balance, Decimal::ZERO);
+        assert_eq!(schedule.meta.total_principal, dec!(18000));
+    }
+
+    #[test]
+    fn test_solve_term_matches_amortised_term() {
+        let spec = builder().build();
+        // The calculated payment is rounded to the penny, so round up to avoid a tiny 49th payment
+        let payment = spec.amortise().unwrap().payments[0].payment + dec!(0.01);
+
+        let schedule = LoanSpec {
+            num_payments: 0,
+            fixed_payment: Some(payment),
+            ..spec
+        }
+        .solve_term()
+        .unwrap();
+
+        assert_eq!(schedule.meta.term, 48);
+    }
+
+    #[test]
+    fn test_solve_term_after_interest_only_periods() {
+        let spec = builder().build();
+        let payment = spec.amortise().unwrap().payments[0].payment + dec!(0.01);
+
+        // A year of interest only, then the same payment repays the untouched balance
+        let schedule = LoanSpec {
+            num_payments: 0,
+            fixed_payment: Some(payment),
+            interest_only_periods: 12,
+            ..spec
+        }
+        .solve_term()
+        .unwrap();
+
+        assert_eq!(schedule.meta.term, 60);
+        assert_eq!(schedule.payments[11].balance, dec!(18000));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_solve_term_payment_too_low() {
+        // Monthly interest on £18,000 at 7.9% is about £118
+        let result = builder().fixed_payment(dec!(100)).build().solve_term();
+
+        assert_eq!(
+            result.unwrap_err(),
+            AmortisationError::PaymentDoesNotAmortise { payment: dec!(100) }
+        );
+    }
+
+    #[test]
+    fn test_solve_principal() {
+        // £300/month for 60 months at 7.9%
+        let spec = LoanSpec {
+            principal: Decimal::ZERO,
+            num_payments: 60,
+            fixed_payment: Some(dec!(300)),
+            ..builder().build()
+        };
+
+        let schedule = spec.solve_principal().unwrap();
+
+        let principal = schedule.meta.total_principal;
+        assert!(
+            principal > dec!(14800) && principal < dec!(15000),
+            "solved principal {}",
+            principal
+        );
+        assert_eq!(schedule.payments.len(), 60);
+        assert!(schedule.payments[..59]
+            .iter()
+            .all(|p| p.payment == dec!(300)));
+        let last = schedule.payments.last().unwrap();
+        assert!(last.payment <= dec!(300) && last.payment > dec!(299.9));
+        assert_eq!(last.balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_solve_principal_round_trips_amortise() {
+        for interest_method in [
+            InterestMethod::Convention30_360,
+            InterestMethod::Actual360,
+            InterestMethod::ActualActualIsda,
+        ] {
+            let spec = builder()
+                .interest_method(interest_method)
+                .first_capitalisation_date(NaiveDate::from_ymd_opt(2023, 1, 17).unwrap())
+                .build();
+            let payment = spec.amortise().unwrap().payments[0].payment;
+
+            let schedule = LoanSpec {
+                principal: Decimal::ZERO,
+                fixed_payment: Some(payment),
+                ..spec
+            }
+            .solve_principal()
+            .unwrap();
+
+            assert!(
+                (schedule.meta.total_principal - dec!(18000)).abs() < dec!(1),
+                "{:?} solved principal {}",
+                interest_method,
+                schedule.meta.total_principal
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve_principal_with_balloon() {
+        let spec = LoanSpec {
+            principal: Decimal::ZERO,
+            fixed_payment: Some(dec!(250)),
+            balloon_payment: Some(dec!(8000)),
+            ..builder().build()
+        };
+
+        let schedule = spec.solve_principal().unwrap();
+
+        let check = LoanSpec {
+            principal: schedule.meta.total_principal,
+            fixed_payment: None,
+            ..spec
+        }
+        .amortise()
+        .unwrap();
+        assert!((check.payments[0].payment - dec!(250)).abs() < dec!(0.02));
+        assert_eq!(schedule.payments.last().unwrap().payment, dec!(8000));
+    }
+
+    #[test]
+    fn test_solve_balloon_round_trips_amortise() {
+        let spec = builder().balloon_payment(dec!(8000)).build();
+        let payment = spec.amortise().unwrap().payments[0].payment;
+
+        let schedule = LoanSpec {
+            balloon_payment: None,
+            fixed_payment: Some(payment),
+            ..spec
+        }
+        .solve_balloon(None)
+        .unwrap();
+
+        let balloon = schedule.meta.balloon_payment.unwrap();
+        assert!(
+            (balloon - dec!(8000)).abs() < dec!(1),
+            "solved balloon {}",
+            balloon
+        );
+        assert!(schedule.payments[..47].iter().all(|p| p.payment == payment));
+        assert_eq!(schedule.payments.last().unwrap().payment, balloon);
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_solve_balloon_capped() {
+        let spec = builder().fixed_payment(dec!(250)).build();
+        let uncapped = spec.solve_balloon(None).unwrap();
+        assert!(uncapped.meta.balloon_payment.unwrap() > dec!(7200));
+
+        // Capping the balloon at 40% of the principal pushes the payment up
+        let capped = spec.solve_balloon(Some(dec!(0.4))).unwrap();
+
+        assert_eq!(capped.meta.balloon_payment, Some(dec!(7200)));
+        assert!(capped.payments[0].payment > dec!(250));
+        assert_eq!(capped.payments.last().unwrap().payment, dec!(7200));
+    }
+
+    #[test]
+    fn test_solve_balloon_not_needed() {
+        let schedule = builder()
+            .fixed_payment(dec!(500))
+            .build()
+            .solve_balloon(None)
+            .unwrap();
+
+        assert_eq!(schedule.meta.balloon_payment, None);
+        assert!(schedule.payments.last().unwrap().payment < dec!(500));
+        assert_eq!(schedule.payments.last().unwrap().balance, Decimal::ZERO);
+    }
+}
@@ -0,0 +1,270 @@
+use super::error::AmortisationError;
+use super::secant::secant_method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+const SECANT_MAX_ITERATIONS: u32 = 8;
+const BRACKET_GROWTH: Decimal = Decimal::from_parts(16, 0, 0, false, 1);
+
+/// Controls when the solvers stop.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolverConfig {
+    /// Largest acceptable residual (final balance miss) for a solution.
+    pub tolerance: Decimal,
+    /// Maximum schedule evaluations for each solver stage.
+    pub max_iterations: u32,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            tolerance: Decimal::new(1, 2),
+            max_iterations: 50,
+        }
+    }
+}
+
+/// How the solver reached its answer, reported in the schedule's `Meta`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SolverReport {
+    /// Total schedule evaluations, including any bracketing fallback.
+    pub iterations: u32,
+    /// Residual at the solution, before the result is rounded.
+    pub residual: Decimal,
+}
+
+/// A solution found by one of the root finders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Root {
+    pub value: Decimal,
+    pub residual: Decimal,
+    pub iterations: u32,
+}
+
+impl From<Root> for SolverReport {
+    fn from(root: Root) -> Self {
+        SolverReport {
+            iterations: root.iterations,
+            residual: root.residual,
+        }
+    }
+}
+
+/// Finds a root of `f`, starting from the guesses `x0` and `x1`.
+///
+/// The secant method is tried first as it's quickest from good guesses. If it fails, the
+/// guesses are widened until `f` changes sign and the root is found by Illinois-style
+/// regula falsi, which always converges once a sign change is bracketed. `x_tolerance` is
+/// how close two estimates must be to count as converged when the residual can't get
+/// under `config.tolerance`, e.g. because the schedule rounds to the penny. An error from
+/// `f` stops the search and is returned as is.
+pub fn find_root<F>(
+    mut f: F,
+    x0: Decimal,
+    x1: Decimal,
+    config: &SolverConfig,
+    x_tolerance: Decimal,
+) -> Result<Root, AmortisationError>
+where
+    F: FnMut(Decimal) -> Result<Decimal, AmortisationError>,
+{
+    let mut evaluations = 0;
+    let mut counted = |x| {
+        evaluations += 1;
+        f(x)
+    };
+
+    let secant = secant_method(
+        &mut counted,
+        x0,
+        x1,
+        config.tolerance,
+        x_tolerance,
+        config.max_iterations.min(SECANT_MAX_ITERATIONS),
+    );
+    let result = match secant {
+        Err(AmortisationError::NonConvergence { .. }) => {
+            expand_bracket(&mut counted, x0, x1, config.max_iterations).and_then(
+                |(a, fa, b, fb)| illinois_method(&mut counted, a, fa, b, fb, config, x_tolerance),
+            )
+        }
+        result => result,
+    };
+
+    result.map(|root| Root {
+        iterations: evaluations,
+        ..root
+    })
+}
+
+/// Widens `[x0, x1]` until `f` changes sign across it, returning the endpoints and their values.
+fn expand_bracket<F>(
+    f: &mut F,
+    x0: Decimal,
+    x1: Decimal,
+    max_iterations: u32,
+) -> Result<(Decimal, Decimal, Decimal, Decimal), AmortisationError>
+where
+    F: FnMut(Decimal) -> Result<Decimal, AmortisationError>,
+{
+    let (mut a, mut b) = if x0 < x1 { (x0, x1) } else { (x1, x0) };
+    if a == b {
+        b = a + Decimal::ONE;
+    }
+    let mut fa = f(a)?;
+    let mut fb = f(b)?;
+
+    for _ in 0..max_iterations {
+        if fa.is_sign_negative() != fb.is_sign_negative() || fa.is_zero() || fb.is_zero() {
+            return Ok((a, fa, b, fb));
+        }
+        // Step away from whichever end is closer to a root
+        let step = BRACKET_GROWTH
+            .checked_mul(b - a)
+            .ok_or(AmortisationError::Overflow)?;
+        if fa.abs() < fb.abs() {
+            a = a.checked_sub(step).ok_or(AmortisationError::Overflow)?;
+            fa = f(a)?;
+        } else {
+            b = b.checked_add(step).ok_or(AmortisationError::Overflow)?;
+            fb = f(b)?;
+        }
+    }
+
+    let last_iterate = if fa.abs() < fb.abs() { a } else { b };
+    Err(AmortisationError::NonConvergence { last_iterate })
+}
+
+/// Regula falsi with the Illinois modification, for a root bracketed by `a` and `b`.
+fn illinois_method<F>(
+    f: &mut F,
+    mut a: Decimal,
+    mut fa: Decimal,
+    mut b: Decimal,
+    mut fb: Decimal,
+    config: &SolverConfig,
+    x_tolerance: Decimal,
+) -> Result<Root, AmortisationError>
+where
+    F: FnMut(Decimal) -> Result<Decimal, AmortisationError>,
+{
+    let best = |a: Decimal, fa: Decimal, b: Decimal, fb: Decimal| {
+        if fa.abs() < fb.abs() {
+            (a, fa)
+        } else {
+            (b, fb)
+        }
+    };
+
+    for _ in 0..config.max_iterations {
+        let (x, fx) = best(a, fa, b, fb);
+        if fx.abs() < config.tolerance || (b - a).abs() < x_tolerance {
+            return Ok(Root {
+                value: x,
+                residual: fx,
+                iterations: 0,
+            });
+        }
+
+        let c = b - fb * (b - a) / (fb - fa);
+        let fc = f(c)?;
+
+        if fc.is_sign_negative() != fb.is_sign_negative() {
+            // The root lies between b and c
+            a = b;
+            fa = fb;
+        } else {
+            // The root lies between a and c; halve fa so a isn't retained forever
+            fa /= Decimal::from(2);
+        }
+        b = c;
+        fb = fc;
+    }
+
+    let (last_iterate, _) = best(a, fa, b, fb);
+    Err(AmortisationError::NonConvergence { last_iterate })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_find_root_uses_secant_for_smooth_functions() {
+        let f = |x: Decimal| Ok(x * x - dec!(2));
+        let root = find_root(f, dec!(1), dec!(2), &SolverConfig::default(), dec!(0.0001)).unwrap();
+
+        assert!((root.value - dec!(1.4142)).abs() < dec!(0.01));
+        assert!(root.residual.abs() < dec!(0.01));
+        assert!(root.iterations <= SECANT_MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn test_find_root_falls_back_to_bracketing() {
+        // A step function defeats the secant method: both guesses give the same value
+        let f = |x: Decimal| Ok(if x < dec!(3.3) { dec!(-1) } else { dec!(1) });
+        let config = SolverConfig {
+            tolerance: dec!(0.01),
+            max_iterations: 200,
+        };
+
+        let root = find_root(f, dec!(0), dec!(1), &config, dec!(0.0001)).unwrap();
+
+        assert!(
+            (root.value - dec!(3.3)).abs() < dec!(0.001),
+            "root {}",
+            root.value
+        );
+    }
+
+    #[test]
+    fn test_find_root_no_sign_change() {
+        let f = |x: Decimal| Ok(x * x + dec!(1));
+        let config = SolverConfig {
+            tolerance: dec!(0.0001),
+            max_iterations: 20,
+        };
+
+        let result = find_root(f, dec!(1), dec!(0), &config, dec!(0.0001));
+
+        assert!(matches!(
+            result,
+            Err(AmortisationError::NonConvergence { .. })
+        ));
+    }
+
+    #[test]
+    fn test_illinois_method() {
+        let mut f = |x: Decimal| Ok(x * x * x - x - dec!(2));
+        let config = SolverConfig {
+            tolerance: dec!(0.000001),
+            max_iterations: 100,
+        };
+
+        let root = illinois_method(
+            &mut f,
+            dec!(1),
+            dec!(-2),
+            dec!(2),
+            dec!(4),
+            &config,
+            dec!(0),
+        )
+        .unwrap();
+
+        assert!((root.value - dec!(1.5213797)).abs() < dec!(0.00001));
+    }
+
+    #[test]
+    fn test_expand_bracket() {
+        let mut f = |x: Decimal| Ok(x - dec!(100));
+
+        let (a, fa, b, fb) = expand_bracket(&mut f, dec!(0), dec!(1), 50).unwrap();
+
+        assert!(a <= dec!(100) && b >= dec!(100));
+        assert_eq!(fa, a - dec!(100));
+        assert_eq!(fb, b - dec!(100));
+    }
+}
@@ -0,0 +1,957 @@
+use super::calendar::{BusinessDayConvention, Calendar};
+use super::day_count::{CustomDayCounter, DayCounter};
+use super::deal::MotorFinanceDeal;
+use super::error::AmortisationError;
+use super::floating::FloatingRate;
+use super::frequency::{DateGenerator, PaymentFrequency};
+use super::interest::{decompound_rate, InterestMethod, InterestType};
+use super::profile::PaymentProfile;
+use super::rate::{RateChange, RateChangePayment};
+use super::rfr::CompoundedRfr;
+use super::solver::SolverConfig;
+use super::style::AmortisationStyle;
+use super::utils::round_decimal;
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Complete description of a loan to be amortised.
+///
+/// A `LoanSpec` can be assembled with [`LoanBuilder`] or deserialised from JSON, so
+/// the same definition can be shared between the CLI, spec files and the WASM front end.
+/// Optional fields fall back to the same defaults as the builder.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoanSpec {
+    pub principal: Decimal,
+    pub annual_rate: Decimal,
+    pub num_payments: u32,
+    pub disbursal_date: NaiveDate,
+    pub first_payment_date: NaiveDate,
+    /// Defaults to the first payment date when not set.
+    #[serde(default)]
+    pub first_capitalisation_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub interest_method: InterestMethod,
+    #[serde(default)]
+    pub interest_type: InterestType,
+    #[serde(default)]
+    pub frequency: PaymentFrequency,
+    /// Later rates, each taking effect from its date. `annual_rate` applies until the first.
+    #[serde(default)]
+    pub rate_changes: Vec<RateChange>,
+    /// Whether rate changes recast the payment or flex the term.
+    #[serde(default)]
+    pub rate_change_payment: RateChangePayment,
+    /// Sets the rate from an index plus a margin instead of `annual_rate`.
+    #[serde(default)]
+    pub floating_rate: Option<FloatingRate>,
+    /// Accrues interest at an overnight rate compounded in arrears instead of `annual_rate`.
+    #[serde(default)]
+    pub compounded_rfr: Option<CompoundedRfr>,
+    /// Number of payments at the start that pay only the interest, leaving the balance
+    /// unchanged. When it equals `num_payments` the principal is repaid with the last one.
+    #[serde(default)]
+    pub interest_only_periods: u32,
+    /// Level payments, or equal principal with the interest on top.
+    #[serde(default)]
+    pub amortisation_style: AmortisationStyle,
+    /// Weights that vary the payment over the term; the solver finds the base payment.
+    #[serde(default)]
+    pub payment_profile: Option<PaymentProfile>,
+    /// Number of payments collected together on the disbursal date, before the
+    /// `num_payments` regular ones, as with a lease's advance rentals.
+    #[serde(default)]
+    pub advance_payments: u32,
+    /// The vehicle deal `principal` is the amount of credit of, for HP and PCP agreements.
+    #[serde(default)]
+    pub deal: Option<MotorFinanceDeal>,
+    #[serde(default)]
+    pub fixed_payment: Option<Decimal>,
+    #[serde(default)]
+    pub balloon_payment: Option<Decimal>,
+    #[serde(default)]
+    pub option_fee: Option<Decimal>,
+    /// Stopping rules for the payment and parameter solvers.
+    #[serde(default)]
+    pub solver: SolverConfig,
+    /// Pin month-based payment and capitalisation dates to the last day of the month.
+    #[serde(default)]
+    pub end_of_month: bool,
+    /// Holidays that payment dates are rolled around; weekends are never business days.
+    #[serde(default)]
+    pub calendar: Calendar,
+    /// How payment and capitalisation dates on non-business days are moved.
+    #[serde(default)]
+    pub business_day_convention: BusinessDayConvention,
+    /// Accrue interest to the adjusted capitalisation dates rather than the unadjusted ones.
+    /// Payment dates are always adjusted.
+    #[serde(default)]
+    pub accrue_to_adjusted_dates: bool,
+    /// House day count convention used in place of `interest_method`. Not serialised.
+    #[serde(skip)]
+    pub custom_day_counter: Option<CustomDayCounter>,
+}
+
+impl LoanSpec {
+    pub fn builder(
+        principal: Decimal,
+        annual_rate: Decimal,
+        num_payments: u32,
+        disbursal_date: NaiveDate,
+        first_payment_date: NaiveDate,
+    ) -> LoanBuilder {
+        LoanBuilder::new(
+            principal,
+            annual_rate,
+            num_payments,
+            disbursal_date,
+            first_payment_date,
+        )
+    }
+
+    /// The date interest is first capitalised, falling back to the first payment date.
+    pub fn capitalisation_date(&self) -> NaiveDate {
+        self.first_capitalisation_date
+            .unwrap_or(self.first_payment_date)
+    }
+
+    /// The day count interest accrues under: the custom day counter if set, otherwise the
+    /// interest method.
+    pub fn day_counter(&self) -> &dyn DayCounter {
+        match &self.custom_day_counter {
+            Some(custom) => custom.0.as_ref(),
+            None => &self.interest_method,
+        }
+    }
+
+    /// The nominal rate interest starts accruing at, decompounded for `Compound` loans.
+    pub fn nominal_rate(&self) -> Decimal {
+        self.nominalise(self.initial_rate())
+    }
+
+    /// `annual_rate` as the nominal rate interest accrues at, decompounded for `Compound` loans.
+    pub fn nominalise(&self, annual_rate: Decimal) -> Decimal {
+        match self.interest_type {
+            InterestType::Compound => decompound_rate(annual_rate, self.frequency),
+            InterestType::Simple => annual_rate,
+        }
+    }
+
+    /// Whether every payment is interest only, with the principal repaid in a bullet.
+    pub fn is_interest_only(&self) -> bool {
+        self.interest_only_periods > 0 && self.interest_only_periods >= self.num_payments
+    }
+
+    /// Number of payments that repay principal, after the interest-only periods.
+    pub fn amortising_payments(&self) -> u32 {
+        self.num_payments.saturating_sub(self.interest_only_periods)
+    }
+
+    /// The principal repaid by each amortising payment of an equal principal loan, leaving any
+    /// balloon payment for the last.
+    pub fn principal_instalment(&self) -> Decimal {
+        let amortised = self.principal - self.balloon_payment.unwrap_or_default();
+        round_decimal(
+            amortised / Decimal::from(self.amortising_payments().max(1)),
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// The weight of payment number `payment` under the payment profile, 1 without one.
+    pub fn payment_weight(&self, payment: u32) -> Decimal {
+        self.payment_profile
+            .as_ref()
+            .map_or(Decimal::ONE, |profile| profile.weight(payment))
+    }
+
+    /// Whether the rate can change over the life of the loan.
+    pub fn has_variable_rate(&self) -> bool {
+        !self.rate_changes.is_empty() || self.floating_rate.is_some()
+    }
+
+    /// The annual rate interest starts at: `annual_rate`, the floating rate set on the
+    /// disbursal date, or the first overnight rate fixing plus the margin.
+    pub fn initial_rate(&self) -> Decimal {
+        // validate checks the index has a fixing for the first reset or day
+        if let Some(rfr) = &self.compounded_rfr {
+            return rfr
+                .initial_rate(self.disbursal_date)
+                .unwrap_or(self.annual_rate);
+        }
+        match &self.floating_rate {
+            Some(floating) => floating
+                .rate_on(self.disbursal_date, &self.calendar)
+                .unwrap_or(self.annual_rate),
+            None => self.annual_rate,
+        }
+    }
+
+    /// The changes to the initial rate taking effect up to `until`, in date order.
+    ///
+    /// A floating rate resets the day after each reset period ends. The first reset period
+    /// ends on the first capitalisation date and the rest every `reset_frequency` after it,
+    /// so resets line up with accrual periods. Only resets that change the rate are returned.
+    pub fn rate_schedule(&self, until: NaiveDate) -> Vec<RateChange> {
+        let Some(floating) = &self.floating_rate else {
+            let mut changes = self.rate_changes.clone();
+            changes.sort_by_key(|change| change.effective_date);
+            return changes;
+        };
+
+        let reset_frequency = floating.reset_frequency.unwrap_or(self.frequency);
+        let period_ends = DateGenerator::new(
+            self.capitalisation_date(),
+            reset_frequency,
+            self.end_of_month,
+        );
+        let mut annual_rate = self.initial_rate();
+        let mut changes = Vec::new();
+        for period in 0.. {
+            let mut period_end = period_ends.date(period);
+            if self.accrue_to_adjusted_dates {
+                period_end = self
+                    .business_day_convention
+                    .adjust(period_end, &self.calendar);
+            }
+            let reset_date = period_end + Days::new(1);
+            if reset_date > until {
+                break;
+            }
+            let reset_rate = floating
+                .rate_on(reset_date, &self.calendar)
+                .unwrap_or(annual_rate);
+            if reset_rate != annual_rate {
+                changes.push(RateChange {
+                    effective_date: reset_date,
+                    annual_rate: reset_rate,
+                });
+                annual_rate = reset_rate;
+            }
+        }
+        changes
+    }
+
+    /// Checks the spec describes a loan that can be amortised.
+    pub fn validate(&self) -> Result<(), AmortisationError> {
+        if self.num_payments == 0 {
+            return Err(AmortisationError::InvalidTerm(self.num_payments));
+        }
+        if self.principal <= Decimal::ZERO {
+            return Err(AmortisationError::InvalidPrincipal(self.principal));
+        }
+        if let Some(deal) = &self.deal {
+            deal.validate()?;
+            if self.principal != deal.amount_of_credit() {
+                return Err(AmortisationError::DealCreditMismatch {
+                    principal: self.principal,
+                    amount_of_credit: deal.amount_of_credit(),
+                });
+            }
+        }
+        if self.interest_only_periods > self.num_payments {
+            return Err(AmortisationError::InterestOnlyExceedsTerm {
+                interest_only_periods: self.interest_only_periods,
+                num_payments: self.num_payments,
+            });
+        }
+        if self.first_payment_date < self.disbursal_date {
+            return Err(AmortisationError::FirstPaymentBeforeDisbursal {
+                disbursal_date: self.disbursal_date,
+                first_payment_date: self.first_payment_date,
+            });
+        }
+        if self.capitalisation_date() < self.disbursal_date {
+            return Err(AmortisationError::CapitalisationBeforeDisbursal {
+                disbursal_date: self.disbursal_date,
+                first_capitalisation_date: self.capitalisation_date(),
+            });
+        }
+        if self.amortisation_style == AmortisationStyle::EqualPrincipal {
+            if self.fixed_payment.is_some() {
+                return Err(AmortisationError::Unsupported(
+                    "a fixed payment on an equal principal loan",
+                ));
+            }
+            if self.has_variable_rate() && self.rate_change_payment == RateChangePayment::FlexTerm {
+                return Err(AmortisationError::Unsupported(
+                    "flexing the term of an equal principal loan",
+                ));
+            }
+            if self.payment_profile.is_some() {
+                return Err(AmortisationError::Unsupported(
+                    "a payment profile on an equal principal loan",
+                ));
+            }
+            if self.advance_payments > 0 {
+                return Err(AmortisationError::Unsupported(
+                    "payments in advance on an equal principal loan",
+                ));
+            }
+        }
+        if let Some(profile) = &self.payment_profile {
+            profile.validate()?;
+        }
+        if self.advance_payments > 0 && self.is_interest_only() {
+            return Err(AmortisationError::Unsupported(
+                "payments in advance on an interest-only loan",
+            ));
+        }
+        if let Some(floating) = &self.floating_rate {
+            if !self.rate_changes.is_empty() {
+                return Err(AmortisationError::Unsupported(
+                    "rate changes on a floating rate loan",
+                ));
+            }
+            if let (Some(floor), Some(cap)) = (floating.floor, floating.cap) {
+                if floor > cap {
+                    return Err(AmortisationError::FloorExceedsCap { floor, cap });
+                }
+            }
+            if floating
+                .rate_on(self.disbursal_date, &self.calendar)
+                .is_none()
+            {
+                return Err(AmortisationError::MissingFixing(
+                    floating.observation_date(self.disbursal_date, &self.calendar),
+                ));
+            }
+        }
+        if let Some(rfr) = &self.compounded_rfr {
+            if self.has_variable_rate() {
+                return Err(AmortisationError::Unsupported(
+                    "rate changes or a floating rate on a compounded RFR loan",
+                ));
+            }
+            if self
+                .day_counter()
+                .fixed_period_days(self.frequency)
+                .is_some()
+            {
+                return Err(AmortisationError::Unsupported(
+                    "compounded RFR interest with a fixed-days day count",
+                ));
+            }
+            if self.interest_type == InterestType::Compound {
+                return Err(AmortisationError::Unsupported(
+                    "compounded RFR interest with a Compound interest type",
+                ));
+            }
+            if rfr.initial_rate(self.disbursal_date).is_none() {
+                return Err(AmortisationError::MissingFixing(
+                    rfr.first_fixing_date(self.disbursal_date),
+                ));
+            }
+        }
+        if let Some(balloon) = self.balloon_payment {
+            if self.has_variable_rate() && self.rate_change_payment == RateChangePayment::FlexTerm {
+                return Err(AmortisationError::Unsupported(
+                    "flexing the term of a loan with a balloon payment",
+                ));
+            }
+            if self.is_interest_only() {
+                return Err(AmortisationError::Unsupported(
+                    "a balloon payment on an interest-only loan",
+                ));
+            }
+            if balloon > self.principal {
+                return Err(AmortisationError::BalloonExceedsPrincipal {
+                    balloon,
+                    principal: self.principal,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`LoanSpec`].
+///
+/// The constructor takes the fields every loan needs; everything else has a named
+/// setter and a default (capitalisation on the payment date, `ActualActualIsda`, `Simple`,
+/// `Monthly`).
+#[derive(Debug, Clone)]
+pub struct LoanBuilder {
+    spec: LoanSpec,
+}
+
+impl LoanBuilder {
+    pub fn new(
+        principal: Decimal,
+        annual_rate: Decimal,
+        num_payments: u32,
+        disbursal_date: NaiveDate,
+        first_payment_date: NaiveDate,
+    ) -> Self {
+        LoanBuilder {
+            spec: LoanSpec {
+                principal,
+                annual_rate,
+                num_payments,
+                disbursal_date,
+                first_payment_date,
+                first_capitalisation_date: None,
+                interest_method: InterestMethod::default(),
+                interest_type: InterestType::default(),
+                frequency: PaymentFrequency::default(),
+                rate_changes: Vec::new(),
+                rate_change_payment: RateChangePayment::default(),
+                floating_rate: None,
+                compounded_rfr: None,
+                interest_only_periods: 0,
+                amortisation_style: AmortisationStyle::default(),
+                payment_profile: None,
+                advance_payments: 0,
+                deal: None,
+                fixed_payment: None,
+                balloon_payment: None,
+                option_fee: None,
+                solver: SolverConfig::default(),
+                custom_day_counter: None,
+                end_of_month: false,
+                calendar: Calendar::default(),
+                business_day_convention: BusinessDayConvention::default(),
+                accrue_to_adjusted_dates: false,
+            },
+        }
+    }
+
+    pub fn first_capitalisation_date(mut self, date: NaiveDate) -> Self {
+        self.spec.first_capitalisation_date = Some(date);
+        self
+    }
+
+    pub fn interest_method(mut self, interest_method: InterestMethod) -> Self {
+        self.spec.interest_method = interest_method;
+        self
+    }
+
+    pub fn interest_type(mut self, interest_type: InterestType) -> Self {
+        self.spec.interest_type = interest_type;
+        self
+    }
+
+    pub fn frequency(mut self, frequency: PaymentFrequency) -> Self {
+        self.spec.frequency = frequency;
+        self
+    }
+
+    /// Change the annual rate to `annual_rate` from `effective_date`.
+    pub fn rate_change(mut self, effective_date: NaiveDate, annual_rate: Decimal) -> Self {
+        self.spec.rate_changes.push(RateChange {
+            effective_date,
+            annual_rate,
+        });
+        self
+    }
+
+    pub fn rate_change_payment(mut self, rate_change_payment: RateChangePayment) -> Self {
+        self.spec.rate_change_payment = rate_change_payment;
+        self
+    }
+
+    /// Set the rate from an index plus a margin; `annual_rate` is then ignored.
+    pub fn floating_rate(mut self, floating_rate: FloatingRate) -> Self {
+        self.spec.floating_rate = Some(floating_rate);
+        self
+    }
+
+    /// Accrue interest at an overnight rate compounded in arrears; `annual_rate` is then
+    /// ignored.
+    pub fn compounded_rfr(mut self, compounded_rfr: CompoundedRfr) -> Self {
+        self.spec.compounded_rfr = Some(compounded_rfr);
+        self
+    }
+
+    /// Pay only interest for the first `periods` payments, then amortise over the rest. With
+    /// `periods` equal to the number of payments the loan is interest only throughout.
+    pub fn interest_only_periods(mut self, periods: u32) -> Self {
+        self.spec.interest_only_periods = periods;
+        self
+    }
+
+    pub fn amortisation_style(mut self, amortisation_style: AmortisationStyle) -> Self {
+        self.spec.amortisation_style = amortisation_style;
+        self
+    }
+
+    /// Vary the payment by `profile`, scaling the solved (or fixed) payment period by period.
+    pub fn payment_profile(mut self, profile: PaymentProfile) -> Self {
+        self.spec.payment_profile = Some(profile);
+        self
+    }
+
+    /// Collect `payments` payments on the disbursal date, before the regular ones, so 3 with
+    /// 35 regular payments is a 3+35 profile and 1 makes the loan an annuity-due.
+    pub fn advance_payments(mut self, payments: u32) -> Self {
+        self.spec.advance_payments = payments;
+        self
+    }
+
+    /// Finance `deal`, setting the principal to its amount of credit.
+    pub fn deal(mut self, deal: MotorFinanceDeal) -> Self {
+        self.spec.principal = deal.amount_of_credit();
+        self.spec.deal = Some(deal);
+        self
+    }
+
+    /// Use this payment for every period instead of solving for one.
+    pub fn fixed_payment(mut self, payment: Decimal) -> Self {
+        self.spec.fixed_payment = Some(payment);
+        self
+    }
+
+    /// Final payment (GMFV) left outstanding at the end of the term, for PCP loans.
+    pub fn balloon_payment(mut self, balloon: Decimal) -> Self {
+        self.spec.balloon_payment = Some(balloon);
+        self
+    }
+
+    /// Fee added to the final payment, for HP loans.
+    pub fn option_fee(mut self, fee: Decimal) -> Self {
+        self.spec.option_fee = Some(fee);
+        self
+    }
+
+    pub fn solver(mut self, solver: SolverConfig) -> Self {
+        self.spec.solver = solver;
+        self
+    }
+
+    /// Pay on the last day of every month (or quarter, half or year), whatever day the first
+    /// payment falls on.
+    pub fn end_of_month(mut self, end_of_month: bool) -> Self {
+        self.spec.end_of_month = end_of_month;
+        self
+    }
+
+    pub fn calendar(mut self, calendar: Calendar) -> Self {
+        self.spec.calendar = calendar;
+        self
+    }
+
+    /// Roll payment dates that fall on weekends or holidays with this convention.
+    pub fn business_day_convention(mut self, convention: BusinessDayConvention) -> Self {
+        self.spec.business_day_convention = convention;
+        self
+    }
+
+    /// Accrue interest to the adjusted capitalisation dates instead of the unadjusted ones.
+    pub fn accrue_to_adjusted_dates(mut self, adjusted: bool) -> Self {
+        self.spec.accrue_to_adjusted_dates = adjusted;
+        self
+    }
+
+    /// Accrue interest under a house day count convention instead of the interest method.
+    pub fn day_counter(mut self, day_counter: impl DayCounter + 'static) -> Self {
+        self.spec.custom_day_counter = Some(CustomDayCounter(Arc::new(day_counter)));
+        self
+    }
+
+    pub fn build(self) -> LoanSpec {
+        self.spec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amortise::floating::RateIndex;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_builder_defaults() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+
+        let spec = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            24,
+            disbursal_date,
+            first_payment_date,
+        )
+        .build();
+
+        assert_eq!(spec.capitalisation_date(), first_payment_date);
+        assert_eq!(spec.interest_method, InterestMethod::ActualActualIsda);
+        assert_eq!(spec.interest_type, InterestType::Simple);
+        assert_eq!(spec.frequency, PaymentFrequency::Monthly);
+        assert_eq!(spec.fixed_payment, None);
+        assert_eq!(spec.balloon_payment, None);
+        assert_eq!(spec.option_fee, None);
+        assert!(!spec.end_of_month);
+        assert_eq!(spec.calendar, Calendar::WeekendsOnly);
+        assert_eq!(
+            spec.business_day_convention,
+            BusinessDayConvention::Unadjusted
+        );
+        assert!(!spec.accrue_to_adjusted_dates);
+        assert_eq!(spec.interest_only_periods, 0);
+        assert_eq!(spec.amortisation_style, AmortisationStyle::Annuity);
+        assert_eq!(spec.advance_payments, 0);
+    }
+
+    #[test]
+    fn test_validate() {
+        let disbursal_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let first_payment_date = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap();
+        let builder = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            24,
+            disbursal_date,
+            first_payment_date,
+        );
+
+        assert_eq!(builder.clone().build().validate(), Ok(()));
+
+        let mut spec = builder.clone().build();
+        spec.num_payments = 0;
+        assert_eq!(spec.validate(), Err(AmortisationError::InvalidTerm(0)));
+
+        let mut spec = builder.clone().build();
+        spec.principal = dec!(-1);
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::InvalidPrincipal(dec!(-1)))
+        );
+
+        let early = NaiveDate::from_ymd_opt(2022, 12, 1).unwrap();
+        let spec = LoanSpec::builder(dec!(15000), dec!(0.05), 24, disbursal_date, early).build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::FirstPaymentBeforeDisbursal {
+                disbursal_date,
+                first_payment_date: early,
+            })
+        );
+
+        let spec = builder.clone().first_capitalisation_date(early).build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::CapitalisationBeforeDisbursal {
+                disbursal_date,
+                first_capitalisation_date: early,
+            })
+        );
+
+        let spec = builder.clone().balloon_payment(dec!(15001)).build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::BalloonExceedsPrincipal {
+                balloon: dec!(15001),
+                principal: dec!(15000),
+            })
+        );
+
+        let spec = builder.clone().interest_only_periods(25).build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::InterestOnlyExceedsTerm {
+                interest_only_periods: 25,
+                num_payments: 24,
+            })
+        );
+        let spec = builder
+            .clone()
+            .interest_only_periods(24)
+            .balloon_payment(dec!(5000))
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::Unsupported(
+                "a balloon payment on an interest-only loan"
+            ))
+        );
+
+        let spec = builder
+            .clone()
+            .amortisation_style(AmortisationStyle::EqualPrincipal)
+            .fixed_payment(dec!(700))
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::Unsupported(
+                "a fixed payment on an equal principal loan"
+            ))
+        );
+
+        let spec = builder
+            .clone()
+            .interest_only_periods(24)
+            .advance_payments(1)
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::Unsupported(
+                "payments in advance on an interest-only loan"
+            ))
+        );
+
+        let index = RateIndex::new([(disbursal_date, dec!(0.05))]);
+        let floating = FloatingRate::new(index, dec!(0.02));
+        let spec = builder
+            .clone()
+            .floating_rate(floating.clone())
+            .rate_change(first_payment_date, dec!(0.06))
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::Unsupported(
+                "rate changes on a floating rate loan"
+            ))
+        );
+
+        let spec = builder
+            .clone()
+            .floating_rate(FloatingRate {
+                floor: Some(dec!(0.01)),
+                cap: Some(dec!(0)),
+                ..floating.clone()
+            })
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::FloorExceedsCap {
+                floor: dec!(0.01),
+                cap: dec!(0),
+            })
+        );
+
+        // Sunday 1 January looks back to Friday 30 December, before the first fixing
+        let spec = builder
+            .clone()
+            .floating_rate(FloatingRate {
+                lookback_days: 1,
+                ..floating
+            })
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::MissingFixing(
+                NaiveDate::from_ymd_opt(2022, 12, 30).unwrap()
+            ))
+        );
+
+        // Sunday 1 January takes Friday's fixing, or Thursday's with a day's lookback
+        let friday = NaiveDate::from_ymd_opt(2022, 12, 30).unwrap();
+        let rfr = CompoundedRfr::new(RateIndex::new([(friday, dec!(0.05))]), dec!(0.02));
+        let builder = builder.interest_method(InterestMethod::Actual365);
+        assert_eq!(
+            builder
+                .clone()
+                .compounded_rfr(rfr.clone())
+                .build()
+                .validate(),
+            Ok(())
+        );
+        let spec = builder
+            .clone()
+            .compounded_rfr(rfr.clone())
+            .interest_type(InterestType::Compound)
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::Unsupported(
+                "compounded RFR interest with a Compound interest type"
+            ))
+        );
+        let spec = builder
+            .compounded_rfr(CompoundedRfr {
+                lookback_days: 1,
+                ..rfr
+            })
+            .build();
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::MissingFixing(
+                NaiveDate::from_ymd_opt(2022, 12, 29).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_rate_schedule() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let builder = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            12,
+            date(2024, 1, 1),
+            date(2024, 2, 1),
+        );
+
+        // Explicit changes come back in date order
+        let spec = builder
+            .clone()
+            .rate_change(date(2024, 9, 1), dec!(0.07))
+            .rate_change(date(2024, 3, 1), dec!(0.06))
+            .build();
+        assert_eq!(spec.initial_rate(), dec!(0.05));
+        assert_eq!(
+            spec.rate_schedule(date(2025, 1, 1))
+                .iter()
+                .map(|change| change.effective_date)
+                .collect::<Vec<_>>(),
+            vec![date(2024, 3, 1), date(2024, 9, 1)]
+        );
+
+        // Quarterly resets on a monthly loan, observed two business days early. The first reset
+        // period ends on the first capitalisation date, and the index is unchanged at the
+        // February and August resets so there are no changes then
+        let index = RateIndex::new([
+            (date(2023, 12, 1), dec!(0.0519)),
+            (date(2024, 4, 1), dec!(0.0495)),
+            (date(2024, 9, 27), dec!(0.0470)),
+        ]);
+        let spec = builder
+            .floating_rate(FloatingRate {
+                reset_frequency: Some(PaymentFrequency::Quarterly),
+                lookback_days: 2,
+                ..FloatingRate::new(index, dec!(0.025))
+            })
+            .build();
+        assert_eq!(spec.initial_rate(), dec!(0.0769));
+        assert_eq!(spec.nominal_rate(), dec!(0.0769));
+        assert_eq!(
+            spec.rate_schedule(date(2025, 1, 1)),
+            vec![
+                RateChange {
+                    effective_date: date(2024, 5, 2),
+                    annual_rate: dec!(0.0745),
+                },
+                RateChange {
+                    effective_date: date(2024, 11, 2),
+                    annual_rate: dec!(0.0720),
+                },
+            ]
+        );
+        assert_eq!(spec.rate_schedule(date(2024, 11, 1)).len(), 1);
+    }
+
+    #[test]
+    fn test_spec_from_json() {
+        let json = r#"{
+            "principal": "15000",
+            "annual_rate": "0.05",
+            "num_payments": 24,
+            "disbursal_date": "2023-01-01",
+            "first_payment_date": "2023-02-01",
+            "interest_method": "Actual365",
+            "balloon_payment": "5000"
+        }"#;
+
+        let spec: LoanSpec = serde_json::from_str(json).unwrap();
+
+        let expected = LoanSpec::builder(
+            dec!(15000),
+            dec!(0.05),
+            24,
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2023, 2, 1).unwrap(),
+        )
+        .interest_method(InterestMethod::Actual365)
+        .balloon_payment(dec!(5000))
+        .build();
+        assert_eq!(spec, expected);
+
+        let round_trip: LoanSpec =
+            serde_json::from_str(&serde_json::to_string(&spec).unwrap()).unwrap();
+        assert_eq!(round_trip, spec);
+    }
+
+    #[test]
+    fn test_floating_rate_spec_from_json() {
+        let json = r#"{
+            "principal": "250000",
+            "annual_rate": "0",
+            "num_payments": 60,
+            "disbursal_date": "2024-01-01",
+            "first_payment_date": "2024-02-01",
+            "floating_rate": {
+                "index": {"2023-12-29": "0.0519", "2024-06-28": "0.0495"},
+                "margin": "0.0225",
+                "reset_frequency": "Quarterly",
+                "floor": "0"
+            }
+        }"#;
+
+        let spec: LoanSpec = serde_json::from_str(json).unwrap();
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let index = RateIndex::new([
+            (date(2023, 12, 29), dec!(0.0519)),
+            (date(2024, 6, 28), dec!(0.0495)),
+        ]);
+        let floating = FloatingRate {
+            reset_frequency: Some(PaymentFrequency::Quarterly),
+            floor: Some(dec!(0)),
+            ..FloatingRate::new(index, dec!(0.0225))
+        };
+        assert_eq!(spec.floating_rate, Some(floating));
+        assert_eq!(spec.initial_rate(), dec!(0.0744));
+    }
+
+    #[test]
+    fn test_payment_profile_spec_from_json() {
+        let json = r#"{
+            "principal": "20000",
+            "annual_rate": "0.06",
+            "num_payments": 36,
+            "disbursal_date": "2024-01-01",
+            "first_payment_date": "2024-02-01",
+            "payment_profile": {"Stepped": {"every": 12, "rate": "0.03"}}
+        }"#;
+
+        let spec: LoanSpec = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            spec.payment_profile,
+            Some(PaymentProfile::Stepped {
+                every: 12,
+                rate: dec!(0.03)
+            })
+        );
+        assert_eq!(spec.payment_weight(13), dec!(1.03));
+
+        let spec = LoanSpec {
+            payment_profile: Some(PaymentProfile::Weights(Vec::new())),
+            ..spec
+        };
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::InvalidPaymentProfile(
+                "no payment weights".to_string()
+            ))
+        );
+        let spec = LoanSpec {
+            amortisation_style: AmortisationStyle::EqualPrincipal,
+            payment_profile: Some(PaymentProfile::Weights(vec![dec!(1)])),
+            ..spec
+        };
+        assert_eq!(
+            spec.validate(),
+            Err(AmortisationError::Unsupported(
+                "a payment profile on an equal principal loan"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_spec_from_json_accepts_actualactual() {
+        let json = r#"{
+            "principal": "15000",
+            "annual_rate": "0.05",
+            "num_payments": 24,
+            "disbursal_date": "2023-01-01",
+            "first_payment_date": "2023-02-01",
+            "interest_method": "ActualActual"
+        }"#;
+
+        let spec: LoanSpec = serde_json::from_str(json).unwrap();
+
+        assert_eq!(spec.interest_method, InterestMethod::ActualActualIsda);
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::error::AmortisationError;
+
+/// How each payment splits between principal and interest.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Serialize, Deserialize)]
+pub enum AmortisationStyle {
+    /// Level payments, with the principal share growing as the interest falls.
+    #[default]
+    Annuity,
+    /// The same principal every period with the interest on top, so payments decline.
+    EqualPrincipal,
+}
+
+impl FromStr for AmortisationStyle {
+    type Err = AmortisationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Annuity" => Ok(AmortisationStyle::Annuity),
+            "EqualPrincipal" => Ok(AmortisationStyle::EqualPrincipal),
+            _ => Err(AmortisationError::UnknownAmortisationStyle(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amortisation_style_from_str() {
+        assert_eq!(
+            "EqualPrincipal".parse(),
+            Ok(AmortisationStyle::EqualPrincipal)
+        );
+        assert_eq!("Annuity".parse(), Ok(AmortisationStyle::Annuity));
+        assert_eq!(
+            "Linear".parse::<AmortisationStyle>(),
+            Err(AmortisationError::UnknownAmortisationStyle(
+                "Linear".to_string()
+            ))
+        );
+    }
+}
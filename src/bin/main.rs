@@ -1,94 +1,397 @@
 use chrono::NaiveDate;
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, ArgGroup, Command};
 use rust_decimal::prelude::*;
 use serde_json::json;
+use std::fmt;
+use std::fs;
+use std::process::ExitCode;
 use std::str::FromStr;
 
-use loan_amortisation_rust::amortise::{amortise, InterestMethod, InterestType, Payment};
+use loan_amortisation_rust::amortise::{
+    AmortisationError, AmortisationStyle, BusinessDayConvention, Calendar, CompoundedRfr,
+    FloatingRate, HolidayList, InterestMethod, InterestType, LoanSpec, Meta, MotorFinanceDeal,
+    Payment, PaymentFrequency, PaymentProfile, RateChangePayment, RateIndex, SolverIteration,
+};
 
-fn main() {
+enum CliError {
+    InvalidArgument { name: &'static str, value: String },
+    MissingArgument(&'static str),
+    Spec(String),
+    Amortisation(AmortisationError),
+}
+
+impl CliError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::InvalidArgument { .. } | CliError::MissingArgument(_) | CliError::Spec(_) => {
+                ExitCode::from(2)
+            }
+            CliError::Amortisation(AmortisationError::NonConvergence { .. }) => ExitCode::from(3),
+            CliError::Amortisation(AmortisationError::Overflow) => ExitCode::from(4),
+            CliError::Amortisation(_) => ExitCode::from(2),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::InvalidArgument { name, value } => {
+                write!(f, "invalid value '{}' for {}", value, name)
+            }
+            CliError::MissingArgument(name) => write!(f, "missing required argument {}", name),
+            CliError::Spec(message) => write!(f, "could not read spec: {}", message),
+            CliError::Amortisation(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<AmortisationError> for CliError {
+    fn from(err: AmortisationError) -> Self {
+        CliError::Amortisation(err)
+    }
+}
+
+fn main() -> ExitCode {
     let matches = parse_arguments();
 
-    let principal = Decimal::from_str(matches.get_one::<String>("principal").unwrap()).unwrap();
-    let annual_rate = Decimal::from_str(matches.get_one::<String>("annual_rate").unwrap()).unwrap()
-        / Decimal::from(100);
-    let num_payments = matches
-        .get_one::<String>("num_payments")
-        .unwrap()
-        .parse::<u32>()
-        .unwrap();
-    let disbursal_date = NaiveDate::parse_from_str(
-        matches.get_one::<String>("disbursal_date").unwrap(),
-        "%Y-%m-%d",
-    )
-    .unwrap();
-    let first_payment_date = NaiveDate::parse_from_str(
-        matches.get_one::<String>("first_payment_date").unwrap(),
-        "%Y-%m-%d",
-    )
-    .unwrap();
-    let first_capitalisation_date = NaiveDate::parse_from_str(
-        matches
-            .get_one::<String>("first_capitalisation_date")
-            .unwrap(),
-        "%Y-%m-%d",
-    )
-    .unwrap();
+    match run(&matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            err.exit_code()
+        }
+    }
+}
+
+fn run(matches: &clap::ArgMatches) -> Result<(), CliError> {
+    let mut spec = match matches.get_one::<String>("spec") {
+        Some(path) => load_spec(path)?,
+        None => spec_from_arguments(matches)?,
+    };
+    // A spec file's payment can be overridden, as when trying payments to solve from
+    if let Some(fixed_payment) = parse_value(matches, "fixed_payment")? {
+        spec.fixed_payment = Some(fixed_payment);
+    }
+    if let Some(tolerance) = parse_value(matches, "tolerance")? {
+        spec.solver.tolerance = tolerance;
+    }
+    if let Some(max_iterations) = parse_value(matches, "max_iterations")? {
+        spec.solver.max_iterations = max_iterations;
+    }
+
+    let output_format = matches.get_one::<String>("output_format").unwrap().as_str();
+
+    let solve_target = matches.get_one::<String>("solve").map(String::as_str);
+    let schedule = if solve_target == Some("rate") {
+        spec.solve_rate()?
+    } else if solve_target == Some("term") {
+        spec.solve_term()?
+    } else if solve_target == Some("principal") {
+        spec.solve_principal()?
+    } else if solve_target == Some("balloon") {
+        let max_balloon_ratio = parse_value::<Decimal>(matches, "max_balloon_percent")?
+            .map(|percent| percent / Decimal::from(100));
+        spec.solve_balloon(max_balloon_ratio)?
+    } else if matches.get_flag("trace") {
+        spec.amortise_with_observer(&mut |i: &SolverIteration| {
+            eprintln!(
+                "iteration {}: payment {} residual {}",
+                i.iteration, i.estimate, i.residual
+            )
+        })?
+    } else {
+        spec.amortise()?
+    };
+    if let (true, Some(report)) = (matches.get_flag("trace"), schedule.meta.solver) {
+        eprintln!(
+            "solved in {} iterations, residual {}",
+            report.iterations, report.residual
+        );
+    }
+    let payments = schedule.payments;
+
+    match output_format {
+        "json" => print_json(&payments),
+        "tsv" => print_tsv(&payments),
+        _ => {
+            print_table(&payments);
+            if let Some(target) = solve_target {
+                print_solution(target, &schedule.meta);
+            }
+            print_deal(&schedule.meta);
+        }
+    }
+    Ok(())
+}
+
+fn load_spec(path: &str) -> Result<LoanSpec, CliError> {
+    let contents = fs::read_to_string(path).map_err(|e| CliError::Spec(e.to_string()))?;
+    serde_json::from_str(&contents).map_err(|e| CliError::Spec(e.to_string()))
+}
+
+fn parse_value<T: FromStr>(
+    matches: &clap::ArgMatches,
+    name: &'static str,
+) -> Result<Option<T>, CliError> {
+    matches
+        .get_one::<String>(name)
+        .map(|value| {
+            value.parse::<T>().map_err(|_| CliError::InvalidArgument {
+                name,
+                value: value.clone(),
+            })
+        })
+        .transpose()
+}
+
+fn parse_date(
+    matches: &clap::ArgMatches,
+    name: &'static str,
+) -> Result<Option<NaiveDate>, CliError> {
+    matches
+        .get_one::<String>(name)
+        .map(|value| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| CliError::InvalidArgument {
+                name,
+                value: value.clone(),
+            })
+        })
+        .transpose()
+}
+
+/// Parses `DATE:RATE` arguments, with the rate as a percentage like `--rate`.
+fn parse_rate_changes(matches: &clap::ArgMatches) -> Result<Vec<(NaiveDate, Decimal)>, CliError> {
+    matches
+        .get_many::<String>("rate_change")
+        .unwrap_or_default()
+        .map(|value| {
+            let invalid = || CliError::InvalidArgument {
+                name: "rate_change",
+                value: value.clone(),
+            };
+            let (date, rate) = value.split_once(':').ok_or_else(invalid)?;
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| invalid())?;
+            let rate = Decimal::from_str(rate).map_err(|_| invalid())?;
+            Ok((date, rate / Decimal::from(100)))
+        })
+        .collect()
+}
+
+/// Reads the motor finance deal arguments, if a cash price is given.
+fn motor_finance_deal(matches: &clap::ArgMatches) -> Result<Option<MotorFinanceDeal>, CliError> {
+    let Some(cash_price) = parse_value(matches, "cash_price")? else {
+        return Ok(None);
+    };
+    let amount =
+        |name| -> Result<Decimal, CliError> { Ok(parse_value(matches, name)?.unwrap_or_default()) };
+    Ok(Some(MotorFinanceDeal {
+        customer_deposit: amount("deposit")?,
+        part_exchange_value: amount("part_exchange")?,
+        part_exchange_settlement: amount("part_exchange_settlement")?,
+        dealer_deposit_contribution: amount("dealer_contribution")?,
+        ..MotorFinanceDeal::new(cash_price)
+    }))
+}
+
+/// Reads the payment profile arguments: a step-up as a percentage, or comma-separated weights.
+fn payment_profile(matches: &clap::ArgMatches) -> Result<Option<PaymentProfile>, CliError> {
+    if let Some(rate) = parse_value::<Decimal>(matches, "step_up")? {
+        return Ok(Some(PaymentProfile::Stepped {
+            every: parse_value(matches, "step_every")?.unwrap_or(12),
+            rate: rate / Decimal::from(100),
+        }));
+    }
+    let Some(value) = matches.get_one::<String>("payment_weights") else {
+        return Ok(None);
+    };
+    value
+        .split(',')
+        .map(|weight| {
+            Decimal::from_str(weight.trim()).map_err(|_| CliError::InvalidArgument {
+                name: "payment_weights",
+                value: value.clone(),
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(|weights| Some(PaymentProfile::Weights(weights)))
+}
+
+/// Reads the floating rate arguments, with the margin, floor and cap as percentages.
+fn floating_rate(matches: &clap::ArgMatches) -> Result<Option<FloatingRate>, CliError> {
+    let Some(path) = matches.get_one::<String>("index") else {
+        return Ok(None);
+    };
+    let percent = |name| -> Result<Option<Decimal>, CliError> {
+        Ok(parse_value::<Decimal>(matches, name)?.map(|value| value / Decimal::from(100)))
+    };
+    let reset_frequency = matches
+        .get_one::<String>("reset_frequency")
+        .map(|value| PaymentFrequency::from_str(value))
+        .transpose()?;
+
+    Ok(Some(FloatingRate {
+        reset_frequency,
+        lookback_days: parse_value(matches, "lookback_days")?.unwrap_or_default(),
+        floor: percent("floor")?,
+        cap: percent("cap")?,
+        ..FloatingRate::new(
+            RateIndex::from_csv_file(path)?,
+            percent("margin")?.unwrap_or_default(),
+        )
+    }))
+}
+
+/// Reads the compounded overnight rate arguments, with the margin as a percentage. The rate's
+/// business days are the loan's calendar.
+fn compounded_rfr(
+    matches: &clap::ArgMatches,
+    calendar: &Calendar,
+) -> Result<Option<CompoundedRfr>, CliError> {
+    let Some(path) = matches.get_one::<String>("rfr") else {
+        return Ok(None);
+    };
+    let margin = parse_value::<Decimal>(matches, "margin")?.unwrap_or_default();
+
+    Ok(Some(CompoundedRfr {
+        lookback_days: parse_value(matches, "lookback_days")?.unwrap_or_default(),
+        observation_shift: matches.get_flag("observation_shift"),
+        calendar: calendar.clone(),
+        ..CompoundedRfr::new(RateIndex::from_csv_file(path)?, margin / Decimal::from(100))
+    }))
+}
+
+/// Parses an argument that may only be omitted when it is the value being solved for.
+fn solvable_value<T: FromStr + Default>(
+    matches: &clap::ArgMatches,
+    name: &'static str,
+    solve_target: &str,
+) -> Result<T, CliError> {
+    match parse_value(matches, name)? {
+        Some(value) => Ok(value),
+        None if matches.get_one::<String>("solve").map(String::as_str) == Some(solve_target) => {
+            Ok(T::default())
+        }
+        None => Err(CliError::MissingArgument(name)),
+    }
+}
+
+fn spec_from_arguments(matches: &clap::ArgMatches) -> Result<LoanSpec, CliError> {
+    // A motor finance deal derives the principal from the cash price and deposits
+    let principal: Decimal = if matches.contains_id("cash_price") {
+        Decimal::ZERO
+    } else {
+        solvable_value(matches, "principal", "principal")?
+    };
+    // A floating rate loan takes its rate from the index instead, and validation checks there's
+    // a fixing for the disbursal date
+    let annual_rate: Decimal = if matches.contains_id("rate_index") {
+        Decimal::ZERO
+    } else {
+        solvable_value::<Decimal>(matches, "annual_rate", "rate")? / Decimal::from(100)
+    };
+    let num_payments: u32 = solvable_value(matches, "num_payments", "term")?;
+    let disbursal_date = parse_date(matches, "disbursal_date")?.unwrap();
+    let first_payment_date = parse_date(matches, "first_payment_date")?.unwrap();
 
     let interest_method = InterestMethod::from_str(
         matches
             .get_one::<String>("interest_method")
             .unwrap()
             .as_str(),
-    )
-    .unwrap();
+    )?;
 
     let interest_type = InterestType::from_str(
         matches
             .get_one::<String>("interest_type")
             .unwrap()
             .as_str(),
-    )
-    .unwrap();
+    )?;
 
-    let output_format = matches.get_one::<String>("output_format").unwrap().as_str();
+    let frequency =
+        PaymentFrequency::from_str(matches.get_one::<String>("frequency").unwrap().as_str())?;
 
-    let fixed_payment = matches
-        .get_one::<String>("fixed_payment")
-        .map(|fp| Decimal::from_str(fp).unwrap());
+    let calendar = match matches.get_one::<String>("holidays") {
+        Some(path) => Calendar::Custom(HolidayList::from_file(path)?),
+        None => Calendar::from_str(matches.get_one::<String>("calendar").unwrap().as_str())?,
+    };
 
-    let balloon_payment = matches
-        .get_one::<String>("balloon_payment")
-        .map(|bp| Decimal::from_str(bp).unwrap());
+    let business_day_convention = BusinessDayConvention::from_str(
+        matches
+            .get_one::<String>("business_day_convention")
+            .unwrap()
+            .as_str(),
+    )?;
 
-    let option_fee = matches
-        .get_one::<String>("option_fee")
-        .map(|of| Decimal::from_str(of).unwrap());
+    let rate_change_payment = RateChangePayment::from_str(
+        matches
+            .get_one::<String>("rate_change_payment")
+            .unwrap()
+            .as_str(),
+    )?;
 
-    let schedule = amortise(
+    let amortisation_style = AmortisationStyle::from_str(
+        matches
+            .get_one::<String>("amortisation_style")
+            .unwrap()
+            .as_str(),
+    )?;
+
+    let compounded_rfr = compounded_rfr(matches, &calendar)?;
+
+    let mut builder = LoanSpec::builder(
         principal,
         annual_rate,
         num_payments,
         disbursal_date,
         first_payment_date,
-        first_capitalisation_date,
-        interest_method,
-        interest_type,
-        fixed_payment,
-        balloon_payment,
-        option_fee,
-    );
-    let payments = schedule.payments;
+    )
+    .interest_method(interest_method)
+    .interest_type(interest_type)
+    .frequency(frequency)
+    .calendar(calendar)
+    .business_day_convention(business_day_convention)
+    .accrue_to_adjusted_dates(matches.get_flag("accrue_to_adjusted_dates"))
+    .end_of_month(matches.get_flag("end_of_month"))
+    .rate_change_payment(rate_change_payment)
+    .amortisation_style(amortisation_style)
+    .advance_payments(parse_value(matches, "advance_payments")?.unwrap_or_default())
+    .interest_only_periods(parse_value(matches, "interest_only_periods")?.unwrap_or_default());
 
-    match output_format {
-        "json" => print_json(&payments),
-        "tsv" => print_tsv(&payments),
-        _ => print_table(&payments),
+    if let Some(floating_rate) = floating_rate(matches)? {
+        builder = builder.floating_rate(floating_rate);
+    }
+    if let Some(compounded_rfr) = compounded_rfr {
+        builder = builder.compounded_rfr(compounded_rfr);
+    }
+    if let Some(profile) = payment_profile(matches)? {
+        builder = builder.payment_profile(profile);
+    }
+    if let Some(deal) = motor_finance_deal(matches)? {
+        builder = builder.deal(deal);
+    }
+    for (effective_date, annual_rate) in parse_rate_changes(matches)? {
+        builder = builder.rate_change(effective_date, annual_rate);
+    }
+
+    if let Some(date) = parse_date(matches, "first_capitalisation_date")? {
+        builder = builder.first_capitalisation_date(date);
+    }
+    if let Some(fp) = parse_value(matches, "fixed_payment")? {
+        builder = builder.fixed_payment(fp);
+    }
+    if let Some(bp) = parse_value(matches, "balloon_payment")? {
+        builder = builder.balloon_payment(bp);
     }
+    if let Some(of) = parse_value(matches, "option_fee")? {
+        builder = builder.option_fee(of);
+    }
+
+    Ok(builder.build())
 }
 
 fn parse_arguments() -> clap::ArgMatches {
-    let matches = Command::new("Loan Amortisation Schedule Calculator")
+    Command::new("Loan Amortisation Schedule Calculator")
         .version("1.0")
         .author("David Neale <david@neale.dev>")
         .about("Calculates loan amortisation schedules")
@@ -97,44 +400,45 @@ fn parse_arguments() -> clap::ArgMatches {
             .long("principal")
             .value_name("PRINCIPAL")
             .help("Sets the principal amount")
-            .required(true))
+            .conflicts_with("cash_price")
+            .required_unless_present_any(["spec", "solve", "cash_price"]))
         .arg(Arg::new("annual_rate")
             .short('r')
             .long("rate")
             .value_name("ANNUAL_RATE")
             .help("Sets the annual interest rate")
-            .required(true))
+            .required_unless_present_any(["spec", "solve", "index", "rfr"]))
         .arg(Arg::new("num_payments")
             .short('n')
             .long("num_payments")
             .value_name("NUM_PAYMENTS")
             .help("Sets the number of payments")
-            .required(true))
+            .required_unless_present_any(["spec", "solve"]))
         .arg(Arg::new("disbursal_date")
             .short('d')
             .long("disbursal_date")
             .value_name("DISBURSAL_DATE")
             .help("Sets the disbursal date (YYYY-MM-DD)")
-            .required(true))
+            .required_unless_present("spec"))
         .arg(Arg::new("first_payment_date")
             .short('f')
             .long("first_payment_date")
             .value_name("FIRST_PAYMENT_DATE")
             .help("Sets the first payment date (YYYY-MM-DD)")
-            .required(true))
+            .required_unless_present("spec"))
         .arg(Arg::new("first_capitalisation_date")
             .short('c')
             .long("first_capitalisation_date")
             .value_name("FIRST_CAPITALISATION_DATE")
-            .help("Sets the first capitalisation date (YYYY-MM-DD)")
-            .required(true))
+            .help("Sets the first capitalisation date (YYYY-MM-DD), defaults to the first payment date")
+            .required(false))
         .arg(Arg::new("interest_method")
             .short('i')
             .long("interest_method")
-            .default_value("ACTUALACTUAL")
+            .default_value("ActualActualIsda")
             .value_name("INTEREST_METHOD")
-            .help("Sets the interest method (Convention30_360, Actual365, Actual360, ActualActual)")
-            .required(true))
+            .help("Sets the interest method (Convention30_360, Convention30_360Us, Convention30E360, Convention30E360Isda, Convention30_360German, Actual365, Actual360, Actual364, Actual365Leap, NoLeap365, Business252, ActualActualIsda, ActualActualIcma)")
+            .required(false))
         .arg(Arg::new("interest_type")
             .short('t')
             .long("interest_type")
@@ -142,6 +446,97 @@ fn parse_arguments() -> clap::ArgMatches {
             .value_name("INTEREST_TYPE")
             .help("Sets the interest type (Simple, Compound)")
             .required(false))
+        .arg(Arg::new("rate_change")
+            .long("rate_change")
+            .value_name("DATE:RATE")
+            .action(ArgAction::Append)
+            .help("Changes the annual rate (in percent) from a YYYY-MM-DD date, may be repeated")
+            .required(false))
+        .arg(Arg::new("rate_change_payment")
+            .long("rate_change_payment")
+            .default_value("Recast")
+            .value_name("TREATMENT")
+            .help("Sets what a rate change does to the payment (Recast, FlexTerm)")
+            .required(false))
+        .arg(Arg::new("index")
+            .long("index")
+            .value_name("FIXINGS_FILE")
+            .help("Sets a floating rate from a CSV file of YYYY-MM-DD,RATE index fixings in percent; --rate is then ignored, as the fixings must cover the disbursal date")
+            .required(false))
+        .arg(Arg::new("rfr")
+            .long("rfr")
+            .value_name("FIXINGS_FILE")
+            .help("Accrues interest at an overnight rate compounded in arrears, from a CSV file of YYYY-MM-DD,RATE daily fixings in percent; --rate is then ignored, as the fixings must cover the disbursal date")
+            .required(false))
+        .group(ArgGroup::new("rate_index")
+            .args(["index", "rfr"]))
+        .arg(Arg::new("observation_shift")
+            .long("observation_shift")
+            .action(ArgAction::SetTrue)
+            .requires("rfr")
+            .help("Weights the overnight rate fixings by the shifted observation period's days"))
+        .arg(Arg::new("margin")
+            .long("margin")
+            .value_name("MARGIN")
+            .requires("rate_index")
+            .help("Sets the margin over the index or overnight rate (as a percentage)")
+            .required(false))
+        .arg(Arg::new("reset_frequency")
+            .long("reset_frequency")
+            .value_name("FREQUENCY")
+            .requires("index")
+            .help("Sets how often the floating rate resets, defaults to the payment frequency")
+            .required(false))
+        .arg(Arg::new("lookback_days")
+            .long("lookback_days")
+            .value_name("DAYS")
+            .requires("rate_index")
+            .help("Sets how many business days before each reset, or each day for --rfr, the rate is observed, defaults to 0")
+            .required(false))
+        .arg(Arg::new("floor")
+            .long("floor")
+            .value_name("FLOOR")
+            .requires("index")
+            .help("Sets the lowest index rate applied (as a percentage)")
+            .required(false))
+        .arg(Arg::new("cap")
+            .long("cap")
+            .value_name("CAP")
+            .requires("index")
+            .help("Sets the highest index rate applied (as a percentage)")
+            .required(false))
+        .arg(Arg::new("frequency")
+            .long("frequency")
+            .default_value("Monthly")
+            .value_name("FREQUENCY")
+            .help("Sets the payment frequency (Weekly, Fortnightly, FourWeekly, Monthly, Quarterly, SemiAnnual, Annual)")
+            .required(false))
+        .arg(Arg::new("end_of_month")
+            .long("end_of_month")
+            .action(ArgAction::SetTrue)
+            .help("Pins payment dates to the last day of the month"))
+        .arg(Arg::new("calendar")
+            .long("calendar")
+            .default_value("WeekendsOnly")
+            .value_name("CALENDAR")
+            .help("Sets the holiday calendar for payment dates (WeekendsOnly, EnglandAndWales, UsFederal, Target2)")
+            .required(false))
+        .arg(Arg::new("holidays")
+            .long("holidays")
+            .value_name("HOLIDAYS_FILE")
+            .conflicts_with("calendar")
+            .help("Reads the holiday calendar from a file of YYYY-MM-DD dates, one per line")
+            .required(false))
+        .arg(Arg::new("business_day_convention")
+            .long("business_day_convention")
+            .default_value("Unadjusted")
+            .value_name("CONVENTION")
+            .help("Sets how payment dates on non-business days are moved (Unadjusted, Following, ModifiedFollowing, Preceding)")
+            .required(false))
+        .arg(Arg::new("accrue_to_adjusted_dates")
+            .long("accrue_to_adjusted_dates")
+            .action(ArgAction::SetTrue)
+            .help("Accrues interest to the adjusted dates instead of the unadjusted ones"))
         .arg(Arg::new("output_format")
             .short('o')
             .long("output_format")
@@ -159,40 +554,174 @@ fn parse_arguments() -> clap::ArgMatches {
             .value_name("BALLOON_PAYMENT")
             .help("Sets a balloon payment amount due at the end (optional, for PCP loans)")
             .required(false))
+        .arg(Arg::new("amortisation_style")
+            .long("amortisation_style")
+            .default_value("Annuity")
+            .value_name("STYLE")
+            .help("Sets how payments repay the principal (Annuity for level payments, EqualPrincipal for equal principal with interest on top)")
+            .required(false))
+        .arg(Arg::new("step_up")
+            .long("step_up")
+            .value_name("PERCENT")
+            .help("Steps the payment up by this percentage every --step_every payments, solving for the first (optional)")
+            .conflicts_with("payment_weights")
+            .required(false))
+        .arg(Arg::new("step_every")
+            .long("step_every")
+            .value_name("PAYMENTS")
+            .help("Sets how many payments there are between step-ups, defaults to 12")
+            .requires("step_up")
+            .required(false))
+        .arg(Arg::new("payment_weights")
+            .long("payment_weights")
+            .value_name("WEIGHTS")
+            .help("Weights each payment in turn against a solved base payment, comma-separated; the last carries on (optional)")
+            .required(false))
+        .arg(Arg::new("advance_payments")
+            .long("advance_payments")
+            .value_name("PAYMENTS")
+            .help("Collects this many payments on the disbursal date before the regular ones, e.g. 3 with 35 payments for 3+35 (optional)")
+            .required(false))
+        .arg(Arg::new("interest_only_periods")
+            .long("interest_only_periods")
+            .value_name("PERIODS")
+            .help("Pays only interest for this many payments before amortising, all of them for an interest-only loan (optional)")
+            .required(false))
+        .arg(Arg::new("cash_price")
+            .long("cash_price")
+            .value_name("CASH_PRICE")
+            .help("Sets the vehicle's cash price, deriving the principal from it less the deposits (optional, for HP and PCP deals)")
+            .required(false))
+        .arg(Arg::new("deposit")
+            .long("deposit")
+            .value_name("DEPOSIT")
+            .help("Sets the customer's cash deposit")
+            .requires("cash_price")
+            .required(false))
+        .arg(Arg::new("part_exchange")
+            .long("part_exchange")
+            .value_name("VALUE")
+            .help("Sets the part-exchange value")
+            .requires("cash_price")
+            .required(false))
+        .arg(Arg::new("part_exchange_settlement")
+            .long("part_exchange_settlement")
+            .value_name("SETTLEMENT")
+            .help("Sets the outstanding finance settled on the part-exchange")
+            .requires("cash_price")
+            .required(false))
+        .arg(Arg::new("dealer_contribution")
+            .long("dealer_contribution")
+            .value_name("CONTRIBUTION")
+            .help("Sets the dealer's deposit contribution")
+            .requires("cash_price")
+            .required(false))
         .arg(Arg::new("option_fee")
             .long("option_fee")
             .value_name("OPTION_FEE")
             .help("Sets an option fee amount for HP loans (optional)")
             .required(false))
-        .get_matches();
+        .arg(Arg::new("spec")
+            .short('s')
+            .long("spec")
+            .value_name("SPEC_FILE")
+            .help("Reads the loan definition from a JSON file instead of the arguments above; --fixed_payment still overrides its payment")
+            .required(false))
+        .group(ArgGroup::new("payment_source")
+            .args(["fixed_payment", "spec"])
+            .multiple(true))
+        .arg(Arg::new("solve")
+            .long("solve")
+            .value_name("TARGET")
+            .value_parser(["rate", "term", "principal", "balloon"])
+            .requires("payment_source")
+            .help("Solves for a loan parameter given --fixed_payment or the spec file's fixed_payment (rate, term, principal, balloon)")
+            .required(false))
+        .arg(Arg::new("max_balloon_percent")
+            .long("max_balloon_percent")
+            .value_name("MAX_BALLOON_PERCENT")
+            .help("Caps a solved balloon payment at this percentage of the principal (optional)")
+            .required(false))
+        .arg(Arg::new("trace")
+            .long("trace")
+            .action(ArgAction::SetTrue)
+            .help("Prints each payment solver iteration to stderr"))
+        .arg(Arg::new("tolerance")
+            .long("tolerance")
+            .value_name("TOLERANCE")
+            .help("Sets the largest final balance miss the solvers accept, defaults to 0.01")
+            .required(false))
+        .arg(Arg::new("max_iterations")
+            .long("max_iterations")
+            .value_name("MAX_ITERATIONS")
+            .help("Sets the maximum solver iterations, defaults to 50")
+            .required(false))
+        .get_matches()
+}
 
-    matches
+fn print_solution(target: &str, meta: &Meta) {
+    match target {
+        "rate" => println!(
+            "\nNominal annual rate: {}%",
+            (meta.annual_rate * Decimal::from(100)).normalize()
+        ),
+        "term" => println!("\nTerm: {} payments", meta.term),
+        "principal" => println!("\nPrincipal: {:.2}", meta.total_principal),
+        "balloon" => match meta.balloon_payment {
+            Some(balloon) => println!("\nBalloon payment: {:.2}", balloon),
+            None => println!("\nNo balloon payment needed"),
+        },
+        _ => {}
+    }
 }
 
-fn print_row(
-    month: u32,
-    payment: Decimal,
-    principal: Decimal,
-    interest: Decimal,
-    balance: Decimal,
-) {
+fn print_deal(meta: &Meta) {
+    let Some(deal) = &meta.deal else {
+        return;
+    };
+    println!("\nCash price: {:.2}", deal.cash_price);
+    println!("Customer deposit: {:.2}", deal.customer_deposit);
+    println!("Part-exchange: {:.2}", deal.part_exchange_value);
     println!(
-        "{:5} | {:7.2} | {:9.2} | {:8.2} | {:17.2}",
-        month, payment, principal, interest, balance
+        "Part-exchange settlement: {:.2}",
+        deal.part_exchange_settlement
+    );
+    println!("Total deposit: {:.2}", meta.total_deposit);
+    println!(
+        "Dealer deposit contribution: {:.2}",
+        meta.dealer_deposit_contribution
+    );
+    println!("Amount of credit: {:.2}", meta.amount_of_credit);
+    println!(
+        "Total charge for credit: {:.2}",
+        meta.total_charge_for_credit
+    );
+    println!("Total amount payable: {:.2}", meta.total_amount_payable);
+}
+
+fn print_row(payment: &Payment) {
+    println!(
+        "{:5} | {:10} | {:10} to {:10} | {:15.2} | {:8.4} | {:7.2} | {:9.2} | {:8.2} | {:17.2}",
+        payment.month,
+        payment.due_date,
+        payment.accrual_start,
+        payment.accrual_end,
+        payment.opening_balance,
+        payment.annual_rate * Decimal::from(100),
+        payment.payment,
+        payment.principal,
+        payment.interest,
+        payment.balance
     );
 }
 
 fn print_table(schedule: &[Payment]) {
     println!("\nAmortisation Schedule:");
-    println!("Month | Payment | Principal | Interest | Remaining Balance");
+    println!(
+        "Month | Due Date   | Accrual Period           | Opening Balance | Rate %   | Payment | Principal | Interest | Remaining Balance"
+    );
     for payment in schedule {
-        print_row(
-            payment.month,
-            payment.payment,
-            payment.principal,
-            payment.interest,
-            payment.balance,
-        );
+        print_row(payment);
     }
 }
 
@@ -202,6 +731,11 @@ fn print_json(schedule: &[Payment]) {
         .map(|p| {
             json!({
                 "month": p.month,
+                "due_date": p.due_date,
+                "accrual_start": p.accrual_start,
+                "accrual_end": p.accrual_end,
+                "opening_balance": p.opening_balance,
+                "annual_rate": p.annual_rate,
                 "payment": p.payment,
                 "principal": p.principal,
                 "interest": p.interest,
@@ -213,11 +747,20 @@ fn print_json(schedule: &[Payment]) {
 }
 
 fn print_tsv(schedule: &[Payment]) {
-    println!("Month\tPayment\tPrincipal\tInterest\tRemaining Balance");
+    println!("Month\tDue Date\tAccrual Start\tAccrual End\tOpening Balance\tAnnual Rate\tPayment\tPrincipal\tInterest\tRemaining Balance");
     for payment in schedule {
         println!(
-            "{}\t{:.2}\t{:.2}\t{:.2}\t{:.2}",
-            payment.month, payment.payment, payment.principal, payment.interest, payment.balance
+            "{}\t{}\t{}\t{}\t{:.2}\t{}\t{:.2}\t{:.2}\t{:.2}\t{:.2}",
+            payment.month,
+            payment.due_date,
+            payment.accrual_start,
+            payment.accrual_end,
+            payment.opening_balance,
+            payment.annual_rate.normalize(),
+            payment.payment,
+            payment.principal,
+            payment.interest,
+            payment.balance
         );
     }
 }
@@ -1,11 +1,14 @@
-use crate::amortise::{amortise, InterestMethod, InterestType};
+use crate::amortise::{
+    AmortisationStyle, InterestMethod, InterestType, LoanSpec, PaymentFrequency,
+};
 use chrono::NaiveDate;
 use rust_decimal::{prelude::FromPrimitive, Decimal};
-use serde_wasm_bindgen::to_value;
+use serde_wasm_bindgen::{from_value, to_value};
 use std::str::FromStr;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn amortise_wasm(
     principal: f64,
     annual_rate: f64,
@@ -18,33 +21,104 @@ pub fn amortise_wasm(
     fixed_payment: Option<f64>,
     balloon_payment: Option<f64>,
     option_fee: Option<f64>,
-) -> JsValue {
-    let principal = Decimal::from_f64(principal).unwrap();
-    let annual_rate = Decimal::from_f64(annual_rate).unwrap() / Decimal::from(100);
+    frequency: Option<String>,
+    amortisation_style: Option<String>,
+) -> Result<JsValue, JsError> {
+    let principal = to_decimal("principal", principal)?;
+    let annual_rate = to_decimal("annual_rate", annual_rate)? / Decimal::from(100);
 
-    let disbursal_date = NaiveDate::parse_from_str(&disbursal_date, "%Y-%m-%d").unwrap();
-    let first_payment_date = NaiveDate::parse_from_str(&first_payment_date, "%Y-%m-%d").unwrap();
+    let disbursal_date = parse_date("disbursal_date", &disbursal_date)?;
+    let first_payment_date = parse_date("first_payment_date", &first_payment_date)?;
     let first_capitalisation_date =
-        NaiveDate::parse_from_str(&first_capitalisation_date, "%Y-%m-%d").unwrap();
-    let interest_method = InterestMethod::from_str(&interest_method).unwrap();
-    let interest_type = InterestType::from_str(&interest_type).unwrap();
+        parse_date("first_capitalisation_date", &first_capitalisation_date)?;
+    let interest_method = InterestMethod::from_str(&interest_method)?;
+    let interest_type = InterestType::from_str(&interest_type)?;
+    let frequency = match frequency {
+        Some(frequency) => PaymentFrequency::from_str(&frequency)?,
+        None => PaymentFrequency::default(),
+    };
+    let amortisation_style = match amortisation_style {
+        Some(style) => AmortisationStyle::from_str(&style)?,
+        None => AmortisationStyle::default(),
+    };
 
-    let fixed_payment_decimal = fixed_payment.map(|fp| Decimal::from_f64(fp).unwrap());
-    let balloon_payment_decimal = balloon_payment.map(|bp| Decimal::from_f64(bp).unwrap());
-    let option_fee_decimal = option_fee.map(|of| Decimal::from_f64(of).unwrap());
-
-    let schedule = amortise(
+    let mut builder = LoanSpec::builder(
         principal,
         annual_rate,
         num_payments,
         disbursal_date,
         first_payment_date,
-        first_capitalisation_date,
-        interest_method,
-        interest_type,
-        fixed_payment_decimal,
-        balloon_payment_decimal,
-        option_fee_decimal,
-    );
-    to_value(&schedule).unwrap()
+    )
+    .first_capitalisation_date(first_capitalisation_date)
+    .interest_method(interest_method)
+    .interest_type(interest_type)
+    .frequency(frequency)
+    .amortisation_style(amortisation_style);
+
+    if let Some(fp) = fixed_payment {
+        builder = builder.fixed_payment(to_decimal("fixed_payment", fp)?);
+    }
+    if let Some(bp) = balloon_payment {
+        builder = builder.balloon_payment(to_decimal("balloon_payment", bp)?);
+    }
+    if let Some(of) = option_fee {
+        builder = builder.option_fee(to_decimal("option_fee", of)?);
+    }
+
+    let schedule = builder.build().amortise()?;
+    Ok(to_value(&schedule)?)
+}
+
+/// Amortises a loan described by a serialised `LoanSpec` object.
+///
+/// Unlike `amortise_wasm`, the annual rate is a fraction (0.05 for 5%), matching
+/// the JSON spec files accepted by the CLI.
+#[wasm_bindgen]
+pub fn amortise_spec_wasm(spec: JsValue) -> Result<JsValue, JsError> {
+    let spec: LoanSpec = from_value(spec)?;
+    Ok(to_value(&spec.amortise()?)?)
+}
+
+fn to_decimal(name: &str, value: f64) -> Result<Decimal, JsError> {
+    Decimal::from_f64(value).ok_or_else(|| JsError::new(&format!("invalid {}: {}", name, value)))
+}
+
+fn parse_date(name: &str, value: &str) -> Result<NaiveDate, JsError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| JsError::new(&format!("invalid {}: '{}'", name, value)))
+}
+
+/// Solves for the nominal annual rate at which the spec's `fixed_payment` amortises the loan.
+#[wasm_bindgen]
+pub fn solve_rate_wasm(spec: JsValue) -> Result<JsValue, JsError> {
+    let spec: LoanSpec = from_value(spec)?;
+    Ok(to_value(&spec.solve_rate()?)?)
+}
+
+/// Solves for the number of `fixed_payment`s needed to repay the loan.
+#[wasm_bindgen]
+pub fn solve_term_wasm(spec: JsValue) -> Result<JsValue, JsError> {
+    let spec: LoanSpec = from_value(spec)?;
+    Ok(to_value(&spec.solve_term()?)?)
+}
+
+/// Solves for the largest principal the spec's `fixed_payment` repays over its term.
+#[wasm_bindgen]
+pub fn solve_principal_wasm(spec: JsValue) -> Result<JsValue, JsError> {
+    let spec: LoanSpec = from_value(spec)?;
+    Ok(to_value(&spec.solve_principal()?)?)
+}
+
+/// Solves for the balloon payment that the spec's `fixed_payment` leaves outstanding,
+/// optionally capped at `max_balloon_ratio` of the principal.
+#[wasm_bindgen]
+pub fn solve_balloon_wasm(
+    spec: JsValue,
+    max_balloon_ratio: Option<f64>,
+) -> Result<JsValue, JsError> {
+    let spec: LoanSpec = from_value(spec)?;
+    let max_balloon_ratio = max_balloon_ratio
+        .map(|ratio| to_decimal("max_balloon_ratio", ratio))
+        .transpose()?;
+    Ok(to_value(&spec.solve_balloon(max_balloon_ratio)?)?)
 }
@@ -185,6 +185,8 @@ function setup(init, amortise_wasm) {
     const first_payment_date = document.getElementById('first_payment_date').value || getNextMonthFirst(disbursal_date);
     const first_capitalisation_date = document.getElementById('cap_date_checkbox').checked ? document.getElementById('first_capitalisation_date').value : first_payment_date;
     const interest_method = document.getElementById('interest_method').value;
+    const frequency = document.getElementById('frequency').value;
+    const amortisation_style = document.getElementById('amortisation_style').value;
 
     const interest_type_rd = document.querySelector('input[name="interest_type"]:checked');
     const interest_type = interest_type_rd ? interest_type_rd.value : null;
@@ -213,6 +215,8 @@ function setup(init, amortise_wasm) {
         fixed_payment,
         balloon_payment,
         option_fee,
+        frequency,
+        amortisation_style,
       );
     }
     catch(e) {
@@ -227,6 +231,15 @@ function setup(init, amortise_wasm) {
       const month = document.createElement('td');
       month.textContent = element.month;
       row.appendChild(month);
+      const dueDate = document.createElement('td');
+      dueDate.textContent = element.due_date;
+      row.appendChild(dueDate);
+      const accrualPeriod = document.createElement('td');
+      accrualPeriod.textContent = `${element.accrual_start} to ${element.accrual_end}`;
+      row.appendChild(accrualPeriod);
+      const openingBalance = document.createElement('td');
+      openingBalance.textContent = element.opening_balance;
+      row.appendChild(openingBalance);
       const payment = document.createElement('td');
       payment.textContent = element.payment;
       row.appendChild(payment);
@@ -242,6 +255,9 @@ function setup(init, amortise_wasm) {
       document.querySelector('table tbody').appendChild(row);
     });
 
+    // Equal principal payments decline, so the first is shown
+    document.getElementById('payment-heading').textContent =
+      amortisation_style === 'EqualPrincipal' ? 'First Payment' : 'Regular Payment';
     updateBoxes(
       schedule.payments[0].payment,
       schedule.meta.total_payable,
@@ -315,7 +331,9 @@ function setup(init, amortise_wasm) {
     ['disbursal_date',
       'first_payment_date',
       'first_capitalisation_date',
-      'interest_method'].forEach(element => {
+      'interest_method',
+      'frequency',
+      'amortisation_style'].forEach(element => {
         document.getElementById(element).addEventListener('change', function () {
           calculate();
         });
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn write_spec(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}.json", name, std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_main"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn solved_rate(output: &Output) -> String {
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("Nominal annual rate: "))
+        .unwrap_or_else(|| panic!("no solved rate in {}", stdout))
+        .to_string()
+}
+
+#[test]
+fn test_solve_rate_from_spec_file() {
+    let spec = write_spec(
+        "solve-rate",
+        r#"{
+            "principal": "18000",
+            "annual_rate": "0",
+            "num_payments": 48,
+            "disbursal_date": "2023-01-01",
+            "first_payment_date": "2023-02-01",
+            "fixed_payment": "450"
+        }"#,
+    );
+    let spec_path = spec.to_str().unwrap();
+
+    // The spec's own payment is solved from without --fixed_payment
+    let from_spec = run(&["--spec", spec_path, "--solve", "rate"]);
+    assert!(
+        from_spec.status.success(),
+        "{}",
+        String::from_utf8_lossy(&from_spec.stderr)
+    );
+
+    // --fixed_payment overrides it, giving a higher rate
+    let overridden = run(&[
+        "--spec",
+        spec_path,
+        "--solve",
+        "rate",
+        "--fixed_payment",
+        "460",
+    ]);
+    assert!(overridden.status.success());
+    assert_ne!(solved_rate(&from_spec), solved_rate(&overridden));
+
+    fs::remove_file(spec).unwrap();
+}
+
+#[test]
+fn test_solve_without_payment_is_usage_error() {
+    let output = run(&[
+        "-p",
+        "18000",
+        "-n",
+        "48",
+        "-d",
+        "2023-01-01",
+        "-f",
+        "2023-02-01",
+        "--solve",
+        "rate",
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--fixed_payment"));
+}
+
+#[test]
+fn test_rate_ignored_with_index() {
+    let fixings = std::env::temp_dir().join(format!("fixings-{}.csv", std::process::id()));
+    fs::write(&fixings, "date,rate\n2022-12-01,4.5\n2023-06-01,5.25\n").unwrap();
+    let fixings_path = fixings.to_str().unwrap();
+    let loan = [
+        "-p",
+        "18000",
+        "-n",
+        "48",
+        "-d",
+        "2023-01-01",
+        "-f",
+        "2023-02-01",
+        "--index",
+        fixings_path,
+        "--trace",
+    ];
+
+    let without_rate = run(&loan);
+    let with_rate = run(&[&loan[..], &["--rate", "5"]].concat());
+    assert!(without_rate.status.success());
+    assert_eq!(without_rate.stdout, with_rate.stdout);
+    // Down to the solver's first estimate
+    assert_eq!(without_rate.stderr, with_rate.stderr);
+
+    fs::remove_file(fixings).unwrap();
+}